@@ -1,5 +1,5 @@
 use anyhow::Result;
-use lib_specbase::{SpecBase, Specfile};
+use lib_specbase::{SpecBase, Specfile, CURRENT_FORMAT_VERSION};
 
 // The example successfully shows:
 //
@@ -21,6 +21,10 @@ fn main() -> Result<()> {
         name: "Example Spec".to_string(),
         description: "An example specification file".to_string(),
         content: "# Example Specification\n\nThis is an example specification.".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
     };
 
     let id = spec_db.create_specfile(&spec)?;
@@ -39,6 +43,10 @@ fn main() -> Result<()> {
         name: "Updated Example".to_string(),
         description: "Updated description".to_string(),
         content: "# Updated Specification\n\nThis specification has been updated.".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
     };
 
     spec_db.update_specfile(id, &updated)?;
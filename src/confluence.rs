@@ -0,0 +1,279 @@
+//! Conversion of markdown content to Confluence storage format
+//!
+//! Confluence stores pages as XHTML, with extensions such as the `code`
+//! macro in the `ac:` namespace. This is a basic line-based converter for
+//! the markdown found in typical specs:
+//!
+//! - ATX headings (`#` to `######`)
+//! - fenced code blocks, written as `code` macros with the info string as
+//!   the language
+//! - bullet (`-`, `*`, `+`) and numbered (`1.`, `1)`) lists; nested items
+//!   are flattened into the surrounding list
+//! - pipe tables, whose first row is a header if it is followed by a
+//!   delimiter row such as `|---|---|`
+//! - inline code, `**strong**` and `*emphasized*` text, and inline links
+//! - thematic breaks (`---`, `***`, `___`)
+//!
+//! Every other line is paragraph text, with consecutive lines joined into
+//! one paragraph. `<`, `>`, `&`, and `"` are escaped everywhere outside code
+//! blocks.
+
+/// Kind of block that stays open across consecutive lines
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Block {
+    None,
+    Paragraph,
+    BulletList,
+    NumberedList,
+    Table,
+}
+
+/// Converts markdown text to Confluence storage format
+pub(crate) fn markdown_to_confluence(markdown: &str) -> String {
+    let mut xhtml = String::with_capacity(markdown.len() * 2);
+    let mut open = Block::None;
+    let mut lines = markdown.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(language) = code_fence(line) {
+            close_block(&mut xhtml, &mut open);
+            let mut code = String::new();
+            for code_line in lines.by_ref() {
+                if code_fence(code_line).is_some() {
+                    break;
+                }
+                code.push_str(code_line);
+                code.push('\n');
+            }
+            push_code_macro(&mut xhtml, language, &code);
+        } else if line.trim().is_empty() {
+            close_block(&mut xhtml, &mut open);
+        } else if let Some((level, title)) = heading(line) {
+            close_block(&mut xhtml, &mut open);
+            xhtml.push_str(&format!("<h{level}>{}</h{level}>\n", convert_inline(title)));
+        } else if is_thematic_break(line) {
+            close_block(&mut xhtml, &mut open);
+            xhtml.push_str("<hr />\n");
+        } else if let Some(cells) = table_row(line) {
+            if open != Block::Table {
+                close_block(&mut xhtml, &mut open);
+                xhtml.push_str("<table><tbody>\n");
+                open = Block::Table;
+                // A delimiter row right after the first row makes it the header
+                let has_header = lines
+                    .peek()
+                    .and_then(|next| table_row(next))
+                    .is_some_and(|next| is_delimiter_row(&next));
+                if has_header {
+                    lines.next();
+                    push_table_row(&mut xhtml, "th", &cells);
+                    continue;
+                }
+            }
+            push_table_row(&mut xhtml, "td", &cells);
+        } else if let Some((list, item)) = list_item(line) {
+            if open != list {
+                close_block(&mut xhtml, &mut open);
+                xhtml.push_str(if list == Block::BulletList {
+                    "<ul>\n"
+                } else {
+                    "<ol>\n"
+                });
+                open = list;
+            }
+            xhtml.push_str(&format!("<li>{}</li>\n", convert_inline(item)));
+        } else if open == Block::Paragraph {
+            xhtml.push(' ');
+            xhtml.push_str(&convert_inline(line.trim()));
+        } else {
+            close_block(&mut xhtml, &mut open);
+            xhtml.push_str("<p>");
+            xhtml.push_str(&convert_inline(line.trim()));
+            open = Block::Paragraph;
+        }
+    }
+    close_block(&mut xhtml, &mut open);
+
+    xhtml
+}
+
+/// Writes the closing tag of the open block, if any
+fn close_block(xhtml: &mut String, open: &mut Block) {
+    xhtml.push_str(match open {
+        Block::None => "",
+        Block::Paragraph => "</p>\n",
+        Block::BulletList => "</ul>\n",
+        Block::NumberedList => "</ol>\n",
+        Block::Table => "</tbody></table>\n",
+    });
+    *open = Block::None;
+}
+
+/// Writes a `code` macro holding `code` verbatim
+fn push_code_macro(xhtml: &mut String, language: &str, code: &str) {
+    xhtml.push_str("<ac:structured-macro ac:name=\"code\">");
+    if !language.is_empty() {
+        xhtml.push_str(&format!(
+            "<ac:parameter ac:name=\"language\">{}</ac:parameter>",
+            escape(language)
+        ));
+    }
+    // `]]>` would end the CDATA section, so it is split across two sections
+    let code = code.replace("]]>", "]]]]><![CDATA[>");
+    xhtml.push_str(&format!(
+        "<ac:plain-text-body><![CDATA[{}]]></ac:plain-text-body>",
+        code
+    ));
+    xhtml.push_str("</ac:structured-macro>\n");
+}
+
+/// Writes a table row with one `tag` element (`th` or `td`) per cell
+fn push_table_row(xhtml: &mut String, tag: &str, cells: &[&str]) {
+    xhtml.push_str("<tr>");
+    for cell in cells {
+        xhtml.push_str(&format!("<{tag}>{}</{tag}>", convert_inline(cell)));
+    }
+    xhtml.push_str("</tr>\n");
+}
+
+/// Returns the info string (e.g. the language) if `line` opens or closes a fenced code block
+fn code_fence(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("```")
+        .or_else(|| line.strip_prefix("~~~"))
+        .map(|info| info.trim_start_matches(['`', '~']).trim())
+}
+
+/// Returns the level and text of an ATX heading such as `## Design`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?;
+    let title = title.trim().trim_end_matches('#').trim_end();
+    (!title.is_empty()).then_some((level, title))
+}
+
+/// Returns true for a line of three or more `-`, `*`, or `_`, e.g. `---`
+fn is_thematic_break(line: &str) -> bool {
+    let marks: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&mark| marks.chars().all(|c| c == mark))
+}
+
+/// Splits a table row such as `| a | b |` into its trimmed cells
+fn table_row(line: &str) -> Option<Vec<&str>> {
+    let row = line.trim().strip_prefix('|')?;
+    let row = row.strip_suffix('|').unwrap_or(row);
+    Some(row.split('|').map(str::trim).collect())
+}
+
+/// Returns true for the row separating the header from the body, e.g. `|---|:-:|`
+fn is_delimiter_row(cells: &[&str]) -> bool {
+    cells
+        .iter()
+        .all(|cell| cell.contains('-') && cell.chars().all(|c| matches!(c, '-' | ':')))
+}
+
+/// Returns the kind of list and the text of a list item such as `- item` or `2. item`
+fn list_item(line: &str) -> Option<(Block, &str)> {
+    let text = line.trim_start();
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| text.strip_prefix(marker))
+    {
+        return Some((Block::BulletList, item.trim()));
+    }
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+    let item = text[digits..]
+        .strip_prefix(". ")
+        .or_else(|| text[digits..].strip_prefix(") "))?;
+    Some((Block::NumberedList, item.trim()))
+}
+
+/// Converts inline code, strong and emphasized text, and inline links to XHTML
+///
+/// All other text is escaped.
+fn convert_inline(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['`', '*', '[']) {
+        converted.push_str(&escape(&rest[..start]));
+        let tail = &rest[start..];
+
+        if let Some(code) = tail.strip_prefix('`') {
+            if let Some(end) = code.find('`').filter(|&end| end > 0) {
+                converted.push_str(&format!("<code>{}</code>", escape(&code[..end])));
+                rest = &code[end + 1..];
+                continue;
+            }
+        } else if let Some(strong) = tail.strip_prefix("**") {
+            if let Some(end) = strong.find("**").filter(|&end| end > 0) {
+                converted.push_str(&format!(
+                    "<strong>{}</strong>",
+                    convert_inline(&strong[..end])
+                ));
+                rest = &strong[end + 2..];
+                continue;
+            }
+        } else if let Some(emphasis) = tail.strip_prefix('*') {
+            if let Some(end) = emphasis.find('*').filter(|&end| end > 0) {
+                converted.push_str(&format!("<em>{}</em>", convert_inline(&emphasis[..end])));
+                rest = &emphasis[end + 1..];
+                continue;
+            }
+        } else if let Some((label, url, len)) = inline_link(tail) {
+            converted.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                escape(url),
+                convert_inline(label)
+            ));
+            rest = &tail[len..];
+            continue;
+        }
+
+        // All markers are ASCII, so this is a char boundary
+        converted.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+
+    converted.push_str(&escape(rest));
+    converted
+}
+
+/// Parses a `[label](url)` link at the start of `text`
+///
+/// Returns the label, the URL, and the number of bytes the link spans.
+fn inline_link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let label = &text[1..label_end];
+    if label.is_empty() || label.contains(['[', ']']) {
+        return None;
+    }
+    let url_start = label_end + 2;
+    let url_len = text[url_start..].find(')')?;
+    let url = &text[url_start..url_start + url_len];
+    Some((label, url, url_start + url_len + 1))
+}
+
+/// Escapes the characters that have a meaning in XHTML text and attributes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
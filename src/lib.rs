@@ -1,8 +1,26 @@
-use anyhow::Result;
-use rusqlite::{params, Connection};
+use anyhow::{Context, Result};
+use regex::Regex;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OpenFlags, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+#[cfg(feature = "confluence")]
+mod confluence;
+mod links;
+mod markdown;
+#[cfg(feature = "rst")]
+mod rst;
+mod sections;
+mod slug;
+
 /// Errors that can occur when working with SpecBase
 #[derive(Error, Debug)]
 pub enum SpecError {
@@ -14,13 +32,121 @@ pub enum SpecError {
     #[error("Specfile not found with ID: {0}")]
     SpecfileNotFound(i64),
 
-    /// Indicates that the config directory could not be created
-    #[error("Failed to create config directory")]
-    ConfigDirError,
+    /// Indicates that a specfile changed since the caller read it
+    #[error("Specfile {id} was modified by someone else since it was read")]
+    ConflictDetected {
+        /// ID of the specfile that changed
+        id: i64,
+    },
+
+    /// Indicates that no specfile has the given slug
+    #[error("Specfile not found with slug: {0}")]
+    SlugNotFound(String),
+
+    /// Indicates that the config directory could not be determined or created
+    #[error(
+        "Failed to create config directory {}: {source}{}",
+        .path.display(),
+        config_dir_hint(.source)
+    )]
+    ConfigDirError {
+        /// The directory that could not be created
+        path: PathBuf,
+        /// The underlying I/O error
+        source: io::Error,
+    },
+
+    /// Indicates that a specfile name doesn't follow the configured naming convention
+    #[error("Specfile name {name:?} does not match the naming convention {pattern:?}")]
+    NameConventionViolation {
+        /// The rejected name
+        name: String,
+        /// The pattern names must match
+        pattern: String,
+    },
+
+    /// Indicates that the database file to open doesn't exist
+    #[error("No database found at {}", .0.display())]
+    DatabaseNotFound(PathBuf),
+
+    /// Indicates that the parent directory of a caller-specified database path could not be created
+    #[error(
+        "Failed to create directory {} for the database: {source}{}",
+        .path.display(),
+        config_dir_hint(.source)
+    )]
+    DatabaseDirError {
+        /// The directory that could not be created
+        path: PathBuf,
+        /// The underlying I/O error
+        source: io::Error,
+    },
+
+    /// Indicates that a query ran longer than the configured timeout and was aborted
+    #[error("Query aborted after exceeding the timeout of {0:?}")]
+    QueryTimeout(Duration),
+
+    /// Indicates that there is no recorded operation that could be undone
+    #[error("Nothing to undo")]
+    NothingToUndo,
+
+    /// Indicates that another specfile already has the name, with unique names enforced
+    #[error("A specfile named {0:?} already exists")]
+    DuplicateName(String),
+
+    /// Indicates that unique names can't be enforced because some names are already taken twice
+    #[error(
+        "Cannot enforce unique specfile names because these are used more than once: {}; \
+         rename or delete the duplicates first",
+        .0.join(", ")
+    )]
+    DuplicateNamesExist(Vec<String>),
+
+    /// Indicates that the database was written by a newer version of SpecBase
+    #[error(
+        "Database schema version {found} is newer than the supported version {supported}; \
+         upgrade SpecBase to open this database"
+    )]
+    UnsupportedSchemaVersion {
+        /// Schema version stored in the database
+        found: i64,
+        /// Newest schema version this version of the library supports
+        supported: i64,
+    },
+
+    /// Indicates that an immutable database has an older schema, which can't be migrated
+    #[error(
+        "Database schema version {found} is older than the required version {required}; \
+         open it writable once to migrate it"
+    )]
+    OutdatedSchemaVersion {
+        /// Schema version stored in the database
+        found: i64,
+        /// Schema version this version of the library works with
+        required: i64,
+    },
+
+    /// Indicates a markdown heading level outside of 1 to 6
+    #[error("Invalid heading level {0}; markdown headings have levels 1 to 6")]
+    InvalidHeadingLevel(u32),
+
+    /// Indicates a change to a database opened with [`SpecBase::init_immutable`]
+    #[error("Database is opened immutable; changes are not allowed")]
+    ReadOnly,
+}
+
+/// Returns an actionable hint for directory errors caused by permissions
+fn config_dir_hint(error: &io::Error) -> &'static str {
+    match error.kind() {
+        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+            " (the directory is not writable; check its permissions, or set SPECBASE_DB or pass --db-path to use another location)"
+        }
+        _ => "",
+    }
 }
 
 /// Represents a specification file in the database
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Specfile {
     /// Unique identifier for the specfile. None if not yet saved to database.
     pub id: Option<i64>,
@@ -30,18 +156,834 @@ pub struct Specfile {
     pub description: String,
     /// Full content of the specification in markdown format
     pub content: String,
+    /// Version of the content structure the specification follows
+    ///
+    /// New specfiles should use [`CURRENT_FORMAT_VERSION`]; use
+    /// [`SpecBase::list_outdated_format`] to find specfiles still written
+    /// in an older structure.
+    #[serde(default = "current_format_version")]
+    pub format_version: i32,
+    /// When the specfile was created, as RFC 3339 text in UTC
+    ///
+    /// Set by the database; `None` for specfiles that haven't been saved.
+    /// Specfiles that predate timestamps carry the time the database was
+    /// upgraded.
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// When the specfile was last changed, as RFC 3339 text in UTC
+    ///
+    /// Set by the database on every create, update, and patch; `None` for
+    /// specfiles that haven't been saved.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    /// Tags that group the specification by area, e.g. "auth" or "billing"
+    ///
+    /// Surrounding whitespace is trimmed and empty or duplicate tags are
+    /// ignored when saving. Specfiles read from the database list their
+    /// tags sorted by name.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Format version of specfiles written with the current content structure
+///
+/// Specfiles created before format versions were tracked are treated as
+/// version 1.
+pub const CURRENT_FORMAT_VERSION: i32 = 1;
+
+fn current_format_version() -> i32 {
+    CURRENT_FORMAT_VERSION
+}
+
+/// Built-in skeleton for new specfiles created with [`Specfile::scaffold`]
+pub const DEFAULT_TEMPLATE: &str = "# {name}
+
+## Overview
+
+## Motivation
+
+## Design
+
+## Alternatives
+";
+
+impl Specfile {
+    /// Returns an opaque version token for the specfile's current state
+    ///
+    /// The token is a hash of the name, description, content, format
+    /// version, and tags, so any change to them produces a different
+    /// version. Pass the version of the specfile you read to
+    /// [`SpecBase::update_specfile_checked`] to detect concurrent edits.
+    /// Tokens are only comparable within the same build of the library and
+    /// should not be persisted.
+    pub fn version(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        self.description.hash(&mut hasher);
+        self.content.hash(&mut hasher);
+        self.format_version.hash(&mut hasher);
+        self.tags.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the number of words in the content
+    ///
+    /// Words are the non-empty runs of characters between Unicode
+    /// whitespace, so markdown markers such as `#` or `-` count as words.
+    pub fn word_count(&self) -> usize {
+        self.content.split_whitespace().count()
+    }
+
+    /// Returns the number of characters in the content
+    ///
+    /// Counts Unicode scalar values, including whitespace and line breaks,
+    /// so `é` is one character even though it takes two bytes in UTF-8.
+    pub fn char_count(&self) -> usize {
+        self.content.chars().count()
+    }
+
+    /// Creates an unsaved specfile whose content is filled in from a template
+    ///
+    /// Every `{name}` and `{description}` placeholder in `template` is replaced
+    /// with the given name and description. Use [`DEFAULT_TEMPLATE`] for the
+    /// standard Overview/Motivation/Design/Alternatives structure.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the new specification
+    /// * `description` - Brief description of the new specification
+    /// * `template` - Markdown skeleton for the content
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, Specfile, DEFAULT_TEMPLATE};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let spec = Specfile::scaffold("New Feature", "What it does", DEFAULT_TEMPLATE);
+    /// let id = spec_db.create_specfile(&spec).unwrap();
+    /// ```
+    pub fn scaffold(name: &str, description: &str, template: &str) -> Specfile {
+        let content = template
+            .replace("{name}", name)
+            .replace("{description}", description);
+        Specfile {
+            id: None,
+            name: name.to_string(),
+            description: description.to_string(),
+            content,
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// A partial change to a specfile
+///
+/// Fields set to `None` keep their current value; fields set to `Some`
+/// replace it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SpecfilePatch {
+    /// New name for the specification
+    pub name: Option<String>,
+    /// New description for the specification
+    pub description: Option<String>,
+    /// New content for the specification
+    pub content: Option<String>,
+}
+
+impl SpecfilePatch {
+    /// Returns true if the patch would not change any field
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.content.is_none()
+    }
+}
+
+/// A specfile without its content, as yielded by [`SpecfileCursor`]
+///
+/// The content is only read from the database when
+/// [`SpecSummary::load_content`] is called. Serializing a summary writes
+/// its metadata fields only.
+#[derive(Clone, Serialize)]
+pub struct SpecSummary<'a> {
+    /// Unique identifier for the specfile
+    pub id: i64,
+    /// Name of the specification
+    pub name: String,
+    /// Brief description of the specification
+    pub description: String,
+    /// Version of the content structure the specification follows
+    pub format_version: i32,
+    /// When the specfile was created, as RFC 3339 text in UTC
+    pub created_at: Option<String>,
+    /// When the specfile was last changed, as RFC 3339 text in UTC
+    pub updated_at: Option<String>,
+    /// Tags of the specification, sorted by name
+    pub tags: Vec<String>,
+    #[serde(skip)]
+    spec_db: &'a SpecBase,
+}
+
+impl SpecSummary<'_> {
+    /// Reads the content of the specfile from the database
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The current content
+    /// * `Err(SpecError::SpecfileNotFound)` - The specfile was deleted in the meantime
+    /// * `Err(Error)` - Other database error occurred
+    pub fn load_content(&self) -> Result<String> {
+        let content = self
+            .spec_db
+            .conn
+            .query_row(
+                "SELECT content FROM specfiles WHERE id = ?1 AND deleted_at IS NULL",
+                params![self.id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SpecError::SpecfileNotFound(self.id))?;
+        Ok(content)
+    }
+}
+
+impl std::fmt::Debug for SpecSummary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpecSummary")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("format_version", &self.format_version)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("tags", &self.tags)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the specfiles of a database that loads content on demand
+///
+/// Created by [`SpecBase::cursor`].
+pub struct SpecfileCursor<'a> {
+    summaries: std::vec::IntoIter<SpecSummary<'a>>,
+}
+
+impl<'a> Iterator for SpecfileCursor<'a> {
+    type Item = SpecSummary<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.summaries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.summaries.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SpecfileCursor<'_> {}
+
+/// Differences between two SpecBase databases
+///
+/// Specfiles are matched by name because IDs are assigned independently in
+/// each database. All name lists are sorted alphabetically.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbDiff {
+    /// Names of specfiles that exist only in this database
+    pub only_in_self: Vec<String>,
+    /// Names of specfiles that exist only in the other database
+    pub only_in_other: Vec<String>,
+    /// Names of specfiles present in both databases with different content
+    pub changed: Vec<String>,
+}
+
+impl DbDiff {
+    /// Returns true if both databases hold the same specfiles
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// How a search query is matched against specfiles
+///
+/// Every search term is matched case-insensitively (for ASCII letters) as a
+/// substring of the name, description, or content, using
+/// `name LIKE '%term%' OR description LIKE '%term%' OR content LIKE '%term%'`.
+/// `%` and `_` in a term are escaped, so they match literally. The modes
+/// differ in what counts as a term and how terms are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryMode {
+    /// The whole query is a single term, so multi-word queries must appear
+    /// verbatim, e.g. `"rate limit"` does not match "limit the rate"
+    #[default]
+    Phrase,
+    /// Each whitespace-separated word is a term, and the conditions of all
+    /// terms are joined with `AND`, so every word must appear somewhere
+    All,
+    /// Each whitespace-separated word is a term, and the conditions of all
+    /// terms are joined with `OR`, so at least one word must appear
+    Any,
+}
+
+/// Field to sort the results of [`SpecBase::query_page`] by
+///
+/// Specfiles with equal values are ordered by ID, so pages never overlap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortKey {
+    /// The ID, which is also the order of creation
+    #[default]
+    Id,
+    /// The name, ignoring the case of ASCII letters
+    Name,
+    /// The time of creation
+    CreatedAt,
+    /// The time of the last change
+    UpdatedAt,
+}
+
+impl SortKey {
+    fn as_sql(self) -> &'static str {
+        match self {
+            SortKey::Id => "id",
+            SortKey::Name => "name COLLATE NOCASE",
+            SortKey::CreatedAt => "created_at",
+            SortKey::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+/// Direction to sort the results of [`SpecBase::query_page`] in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortDirection {
+    /// Smallest values first
+    #[default]
+    Ascending,
+    /// Largest values first
+    Descending,
+}
+
+impl SortDirection {
+    fn as_sql(self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "ASC",
+            SortDirection::Descending => "DESC",
+        }
+    }
+}
+
+/// Filters, sort order, and page selection for [`SpecBase::query_page`]
+///
+/// All filters are optional and combined with `AND`. Missing fields take
+/// their default values when deserializing, so the parameters of a request
+/// can be parsed directly into this struct.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryParams {
+    /// Text to search for in the name, description, and content, as in
+    /// [`SpecBase::query_specfiles_with_mode`]; `None` matches every specfile
+    pub query: Option<String>,
+    /// How `query` is matched
+    pub mode: QueryMode,
+    /// Tags a specfile must all have
+    pub tags: Vec<String>,
+    /// Field to sort by
+    pub sort: SortKey,
+    /// Direction to sort in
+    pub direction: SortDirection,
+    /// Number of the page to return, starting at 1; 0 is treated as 1
+    pub page: u32,
+    /// Maximum number of specfiles per page; 0 is treated as 1
+    pub per_page: u32,
+}
+
+impl Default for QueryParams {
+    fn default() -> Self {
+        QueryParams {
+            query: None,
+            mode: QueryMode::default(),
+            tags: Vec::new(),
+            sort: SortKey::default(),
+            direction: SortDirection::default(),
+            page: 1,
+            per_page: 50,
+        }
+    }
+}
+
+/// One page of results and the total number of results across all pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PagedResult<T> {
+    /// The results of the page, at most `per_page` of them
+    pub items: Vec<T>,
+    /// Number of results across all pages
+    pub total: u64,
+    /// Number of the page, starting at 1
+    pub page: u32,
+    /// Maximum number of results per page
+    pub per_page: u32,
+}
+
+impl<T> PagedResult<T> {
+    /// Returns the number of pages needed to show all results
+    pub fn page_count(&self) -> u64 {
+        self.total.div_ceil(u64::from(self.per_page.max(1)))
+    }
+}
+
+/// Runtime settings of the database connection, as reported by SQLite pragmas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PragmaInfo {
+    /// Journal mode, e.g. `delete` or `wal`
+    pub journal_mode: String,
+    /// Synchronous level: 0 = OFF, 1 = NORMAL, 2 = FULL, 3 = EXTRA
+    pub synchronous: i64,
+    /// Milliseconds to wait for a lock before failing with "database is locked"
+    pub busy_timeout: i64,
+    /// Page cache size; positive values are pages, negative values are KiB
+    pub cache_size: i64,
+    /// Size of a database page in bytes
+    pub page_size: i64,
+}
+
+/// How aggressively a WAL checkpoint copies pages back into the database file
+///
+/// See <https://www.sqlite.org/pragma.html#pragma_wal_checkpoint>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckpointMode {
+    /// Copy as many pages as possible without waiting for readers or writers
+    Passive,
+    /// Wait for writers, then copy all pages
+    Full,
+    /// Like `Full`, and also wait for readers so the WAL restarts from the beginning
+    Restart,
+    /// Like `Restart`, and also truncate the WAL file to zero bytes
+    Truncate,
+}
+
+impl CheckpointMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Restart => "RESTART",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+/// Outcome of a WAL checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointResult {
+    /// True if the checkpoint could not finish because the database was busy
+    pub busy: bool,
+    /// Number of pages in the WAL, or -1 if the database is not in WAL mode
+    pub log_pages: i64,
+    /// Number of WAL pages written back to the database, or -1 if not in WAL mode
+    pub checkpointed_pages: i64,
+}
+
+impl CheckpointResult {
+    /// Returns the number of WAL pages not yet written back to the database
+    pub fn remaining_pages(&self) -> i64 {
+        (self.log_pages - self.checkpointed_pages).max(0)
+    }
+}
+
+/// Kind of change recorded in a [`TimelineEntry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimelineEvent {
+    /// The specfile was created
+    Created,
+    /// The specfile was last updated
+    Updated,
+}
+
+impl std::fmt::Display for TimelineEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimelineEvent::Created => write!(f, "created"),
+            TimelineEvent::Updated => write!(f, "updated"),
+        }
+    }
+}
+
+/// A single event in the activity timeline returned by [`SpecBase::timeline`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    /// RFC 3339 timestamp of the event
+    pub timestamp: String,
+    /// ID of the specfile
+    pub specfile_id: i64,
+    /// Current name of the specfile
+    pub name: String,
+    /// Whether the specfile was created or updated
+    pub event: TimelineEvent,
 }
 
 /// Main struct for interacting with the SpecBase database
 pub struct SpecBase {
     conn: Connection,
+    name_pattern: Option<NamePattern>,
+    query_timeout: Option<QueryTimeout>,
+    read_only: bool,
+}
+
+/// Wall-clock limit for searches and listings, enforced by a progress handler
+///
+/// The handler only aborts while `deadline` is set, which
+/// [`SpecBase::with_query_timeout`] does for the duration of an operation.
+struct QueryTimeout {
+    limit: Duration,
+    deadline: Arc<Mutex<Option<Instant>>>,
+}
+
+/// A compiled naming convention together with the pattern it was built from
+struct NamePattern {
+    source: String,
+    regex: Regex,
+}
+
+/// Builder for configuring how a SpecBase database is opened
+///
+/// Use [`SpecBase::builder`] to create one. The default configuration is
+/// identical to [`SpecBase::init`].
+#[derive(Debug, Default, Clone)]
+pub struct SpecBaseBuilder {
+    autoincrement: bool,
+    path: Option<PathBuf>,
+    name_pattern: Option<String>,
+    query_timeout: Option<Duration>,
+    unique_names: bool,
+}
+
+impl SpecBaseBuilder {
+    /// Creates a builder with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares the `id` column with `AUTOINCREMENT` so IDs are never reused
+    ///
+    /// By default SQLite assigns `max(id) + 1` to new rows, so deleting the
+    /// most recent specfile and creating another one hands out the same ID
+    /// again. External references to the deleted spec would then silently
+    /// point at the new one. With `AUTOINCREMENT`, SQLite tracks the largest
+    /// ID ever issued in the internal `sqlite_sequence` table and never
+    /// reuses it. The cost is an extra table write on every insert and IDs
+    /// that can no longer be reclaimed.
+    ///
+    /// # Migration note
+    /// The flag only takes effect when the `specfiles` table is created.
+    /// Opening an existing database with this flag does not alter its schema;
+    /// export the data and re-import it into a fresh database instead.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to create the table with `AUTOINCREMENT`
+    pub fn autoincrement(mut self, enabled: bool) -> Self {
+        self.autoincrement = enabled;
+        self
+    }
+
+    /// Opens the database file at `path` instead of the default location
+    ///
+    /// Missing parent directories are created; if that fails, opening returns
+    /// [`SpecError::DatabaseDirError`]. See [`SpecBase::default_path`] for the
+    /// location used when no path is set.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the SQLite database file
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Requires specfile names to match a regular expression
+    ///
+    /// The whole name must match, e.g. `[A-Z]+-[0-9]{3}-.+` accepts
+    /// `AUTH-001-login` but rejects `login`. Creating, updating, patching, or
+    /// importing a specfile with a non-matching name fails with
+    /// [`SpecError::NameConventionViolation`]. Existing names are not
+    /// checked. Without a pattern, any name is accepted.
+    ///
+    /// The pattern is compiled once when the database is opened; an invalid
+    /// pattern makes [`SpecBaseBuilder::init`] fail.
+    ///
+    /// # Arguments
+    /// * `pattern` - Regular expression in the syntax of the `regex` crate
+    pub fn name_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.name_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Rejects specfiles whose name another specfile already has
+    ///
+    /// When the database is opened, a unique index on the names of all
+    /// specfiles outside the trash is created. Creating, updating, cloning,
+    /// or restoring a specfile under a taken name then fails with
+    /// [`SpecError::DuplicateName`]. If some names are already used more
+    /// than once, opening fails with [`SpecError::DuplicateNamesExist`]
+    /// listing them; open the database without this option to fix them.
+    ///
+    /// # Migration note
+    /// The index stays in the database once created, so names remain unique
+    /// even when the database is later opened without this option.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to enforce unique names
+    pub fn unique_names(mut self, enabled: bool) -> Self {
+        self.unique_names = enabled;
+        self
+    }
+
+    /// Aborts searches and listings that run longer than `limit`
+    ///
+    /// Guards against runaway scans of large databases. The limit applies
+    /// to each call of [`SpecBase::list_specfiles`],
+    /// [`SpecBase::list_specfiles_paged`], [`SpecBase::list_specfiles_by_tag`],
+    /// [`SpecBase::read_by_name_glob`], [`SpecBase::related_by_tags`],
+    /// [`SpecBase::query_specfiles`], [`SpecBase::query_ids`] and their
+    /// `_with_mode` variants, [`SpecBase::count_query`],
+    /// [`SpecBase::search_specfiles`], [`SpecBase::query_page`], and
+    /// [`SpecBase::vocabulary`], which then fail with
+    /// [`SpecError::QueryTimeout`]. Writes are never aborted. Without a
+    /// limit, which is the default, queries run to completion.
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum wall-clock time of a single operation
+    pub fn query_timeout(mut self, limit: Duration) -> Self {
+        self.query_timeout = Some(limit);
+        self
+    }
+
+    /// Opens the database with the configured options
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully initialized database connection
+    /// * `Err(Error)` - Failed to create the database directory or initialize database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::builder()
+    ///     .autoincrement(true)
+    ///     .init()
+    ///     .expect("Failed to initialize database");
+    /// ```
+    pub fn init(self) -> Result<SpecBase> {
+        let custom_path = self.path.is_some();
+        let db_path = match &self.path {
+            Some(path) => path.clone(),
+            None => SpecBase::default_path()?,
+        };
+        if let Some(dir) = db_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|source| {
+                let path = dir.to_path_buf();
+                if custom_path {
+                    SpecError::DatabaseDirError { path, source }
+                } else {
+                    SpecError::ConfigDirError { path, source }
+                }
+            })?;
+        }
+
+        let conn = Connection::open(&db_path)?;
+        self.open(conn)
+    }
+
+    /// Opens an existing database with the configured options
+    ///
+    /// Unlike [`SpecBaseBuilder::init`], this never creates the database
+    /// file or its directory, so a mistyped path or a missing `spec init`
+    /// is reported instead of silently starting with an empty database. An
+    /// existing database is migrated to the current schema as usual.
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully opened database connection
+    /// * `Err(SpecError::DatabaseNotFound)` - There is no database file at the path
+    /// * `Err(Error)` - Failed to open or migrate the database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::builder()
+    ///     .path("docs/specs/specbase.db")
+    ///     .open_existing()
+    ///     .expect("Failed to open database");
+    /// ```
+    pub fn open_existing(self) -> Result<SpecBase> {
+        let db_path = match &self.path {
+            Some(path) => path.clone(),
+            None => SpecBase::default_path()?,
+        };
+        if !db_path.is_file() {
+            return Err(SpecError::DatabaseNotFound(db_path).into());
+        }
+
+        // Without SQLITE_OPEN_CREATE, a file removed after the check isn't recreated either
+        let conn = Connection::open_with_flags(
+            &db_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        self.open(conn)
+    }
+
+    /// Opens an existing database file in immutable mode with the configured options
+    ///
+    /// See [`SpecBase::init_immutable`] for what immutable mode means. The
+    /// database is neither created nor migrated, so `autoincrement`,
+    /// `name_pattern`, and `unique_names` have no effect, but a
+    /// [`SpecBaseBuilder::query_timeout`] applies as usual.
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully opened database connection
+    /// * `Err(SpecError::UnsupportedSchemaVersion)` - The database was created by a newer SpecBase
+    /// * `Err(SpecError::OutdatedSchemaVersion)` - The database needs a migration first
+    /// * `Err(SpecError::DatabaseNotFound)` - There is no database file at the path
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    /// use std::time::Duration;
+    ///
+    /// let spec_db = SpecBase::builder()
+    ///     .path("/mnt/backup/specbase.db")
+    ///     .query_timeout(Duration::from_secs(5))
+    ///     .open_immutable()
+    ///     .expect("Failed to open database");
+    /// ```
+    pub fn open_immutable(self) -> Result<SpecBase> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => SpecBase::default_path()?,
+        };
+        if !path.is_file() {
+            return Err(SpecError::DatabaseNotFound(path).into());
+        }
+        let path_str = path
+            .to_str()
+            .with_context(|| format!("Database path {} is not valid UTF-8", path.display()))?;
+        // `?`, `#`, and `%` would otherwise be read as part of the URI syntax
+        let uri = format!(
+            "file:{}?immutable=1",
+            path_str
+                .replace('%', "%25")
+                .replace('?', "%3f")
+                .replace('#', "%23")
+        );
+        let conn = Connection::open_with_flags(
+            uri,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(SpecError::UnsupportedSchemaVersion {
+                found: version,
+                supported: SCHEMA_VERSION,
+            }
+            .into());
+        }
+        if version < SCHEMA_VERSION {
+            return Err(SpecError::OutdatedSchemaVersion {
+                found: version,
+                required: SCHEMA_VERSION,
+            }
+            .into());
+        }
+
+        let query_timeout = self
+            .query_timeout
+            .map(|limit| QueryTimeout::install(&conn, limit));
+
+        Ok(SpecBase {
+            conn,
+            name_pattern: None,
+            query_timeout,
+            read_only: true,
+        })
+    }
+
+    /// Opens a new in-memory database with the configured options
+    ///
+    /// The database lives only as long as the returned SpecBase and is not
+    /// shared with any other connection. It supports every operation of a
+    /// file-backed database, which makes it a fast, isolated backend for tests
+    /// and throwaway data. The `path` option is ignored.
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully initialized database connection
+    /// * `Err(Error)` - Failed to initialize database
+    ///
+    /// # Example
+    /// ```
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::builder().autoincrement(true).init_in_memory().unwrap();
+    /// assert!(spec_db.path().is_none());
+    /// ```
+    pub fn init_in_memory(self) -> Result<SpecBase> {
+        let conn = Connection::open_in_memory()?;
+        self.open(conn)
+    }
+
+    /// Creates or migrates the schema on `conn` and wraps it in a SpecBase
+    fn open(self, conn: Connection) -> Result<SpecBase> {
+        let name_pattern = match self.name_pattern {
+            Some(source) => {
+                let regex = Regex::new(&format!("^(?:{})$", source))
+                    .with_context(|| format!("Invalid name pattern {:?}", source))?;
+                Some(NamePattern { source, regex })
+            }
+            None => None,
+        };
+
+        run_migrations(&conn, self.autoincrement)?;
+        if self.unique_names {
+            enforce_unique_names(&conn)?;
+        }
+
+        let query_timeout = self
+            .query_timeout
+            .map(|limit| QueryTimeout::install(&conn, limit));
+
+        Ok(SpecBase {
+            conn,
+            name_pattern,
+            query_timeout,
+            read_only: false,
+        })
+    }
+}
+
+impl QueryTimeout {
+    /// Installs the progress handler that enforces `limit` on `conn`
+    fn install(conn: &Connection, limit: Duration) -> Self {
+        let deadline = Arc::new(Mutex::new(None::<Instant>));
+        let handler_deadline = Arc::clone(&deadline);
+        // Called by SQLite every 1000 virtual machine instructions; returning true aborts
+        conn.progress_handler(
+            1000,
+            Some(move || {
+                handler_deadline
+                    .lock()
+                    .is_ok_and(|deadline| deadline.is_some_and(|at| Instant::now() >= at))
+            }),
+        );
+        QueryTimeout { limit, deadline }
+    }
 }
 
 impl SpecBase {
     /// Initializes a new SpecBase instance with a SQLite database
     ///
     /// Creates a new database file at ~/.config/specbase/specbase.db if it doesn't exist.
-    /// Also creates the necessary tables for storing specfiles.
+    /// Also creates the necessary tables for storing specfiles. Use
+    /// [`SpecBase::init_at`] to open a database somewhere else.
     ///
     /// # Returns
     /// * `Ok(SpecBase)` - Successfully initialized database connection
@@ -54,257 +996,3892 @@ impl SpecBase {
     /// let spec_db = SpecBase::init().expect("Failed to initialize database");
     /// ```
     pub fn init() -> Result<Self> {
-        let config_dir = dirs::config_dir()
-            .ok_or(SpecError::ConfigDirError)?
-            .join("specbase");
-        std::fs::create_dir_all(&config_dir)?;
+        // Like `init_at(default_path())`, but reports `ConfigDirError` for the config directory
+        SpecBaseBuilder::new().init()
+    }
 
-        let db_path = config_dir.join("specbase.db");
-        let conn = Connection::open(&db_path)?;
+    /// Initializes a SpecBase instance with the database file at `path`
+    ///
+    /// Opens the database if it exists, or creates it along with any missing
+    /// parent directories. Use this to keep several independent databases,
+    /// e.g. one per project. For more options, see [`SpecBase::builder`].
+    ///
+    /// # Arguments
+    /// * `path` - Path of the SQLite database file
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully initialized database connection
+    /// * `Err(Error)` - [`SpecError::DatabaseDirError`] if the parent directory
+    ///   can't be created, or a database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init_at("docs/specs/specbase.db")
+    ///     .expect("Failed to initialize database");
+    /// ```
+    pub fn init_at(path: impl AsRef<Path>) -> Result<Self> {
+        SpecBaseBuilder::new().path(path.as_ref()).init()
+    }
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS specfiles (
-                id INTEGER PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL,
-                content TEXT NOT NULL
-            )",
+    /// Opens the existing database at the default location
+    ///
+    /// Fails instead of creating the database if it doesn't exist yet; see
+    /// [`SpecBaseBuilder::open_existing`].
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully opened database connection
+    /// * `Err(SpecError::DatabaseNotFound)` - No database has been created yet
+    /// * `Err(Error)` - Failed to open or migrate the database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, SpecError};
+    ///
+    /// match SpecBase::open_existing() {
+    ///     Ok(spec_db) => println!("{} specs", spec_db.count_specfiles().unwrap()),
+    ///     Err(e) if matches!(e.downcast_ref(), Some(SpecError::DatabaseNotFound(_))) => {
+    ///         println!("Run `spec init` first")
+    ///     }
+    ///     Err(e) => panic!("Failed to open database: {e}"),
+    /// }
+    /// ```
+    pub fn open_existing() -> Result<Self> {
+        SpecBaseBuilder::new().open_existing()
+    }
+
+    /// Opens the existing database file at `path`
+    ///
+    /// Fails instead of creating the database if it doesn't exist; see
+    /// [`SpecBaseBuilder::open_existing`].
+    ///
+    /// # Arguments
+    /// * `path` - Path of the SQLite database file
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully opened database connection
+    /// * `Err(SpecError::DatabaseNotFound)` - There is no database file at `path`
+    /// * `Err(Error)` - Failed to open or migrate the database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::open_existing_at("docs/specs/specbase.db")
+    ///     .expect("Failed to open database");
+    /// ```
+    pub fn open_existing_at(path: impl AsRef<Path>) -> Result<Self> {
+        SpecBaseBuilder::new().path(path.as_ref()).open_existing()
+    }
+
+    /// Opens an existing database file in immutable mode
+    ///
+    /// This is a safe mode for inspecting a database without any risk of
+    /// changing it: the file is opened with the SQLite URI parameter
+    /// `immutable=1`, and every method that would change data returns
+    /// [`SpecError::ReadOnly`] before touching the database.
+    ///
+    /// Immutable goes further than a plain read-only connection
+    /// (`mode=ro` or `SQLITE_OPEN_READ_ONLY`). A read-only connection still
+    /// takes locks and sees the changes other connections commit. An
+    /// immutable connection promises SQLite that nobody changes the file, so
+    /// it takes no locks and never checks for changes. That makes it work on
+    /// read-only media and file systems without locking support, but it may
+    /// return stale or wrong results, or fail, if another process writes
+    /// while it's open. Only use it on databases nobody else is writing to.
+    ///
+    /// The database is never created or migrated, so it must already exist
+    /// with the current schema version. Use [`SpecBaseBuilder::open_immutable`]
+    /// to combine immutable mode with a query timeout.
+    ///
+    /// # Arguments
+    /// * `path` - Path of an existing database file
+    ///
+    /// # Returns
+    /// * `Ok(SpecBase)` - Successfully opened database connection
+    /// * `Err(SpecError::UnsupportedSchemaVersion)` - The database was created by a newer SpecBase
+    /// * `Err(SpecError::OutdatedSchemaVersion)` - The database needs a migration first
+    /// * `Err(SpecError::DatabaseNotFound)` - There is no database file at `path`
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init_immutable("docs/specs/specbase.db")
+    ///     .expect("Failed to open database");
+    /// println!("{} specfiles", spec_db.count_specfiles().unwrap());
+    /// ```
+    pub fn init_immutable(path: impl AsRef<Path>) -> Result<Self> {
+        SpecBaseBuilder::new().path(path.as_ref()).open_immutable()
+    }
+
+    /// Initializes a SpecBase instance backed by a new in-memory database
+    ///
+    /// Nothing is written to disk, and all data is lost when the SpecBase is
+    /// dropped. See [`SpecBaseBuilder::init_in_memory`] for details.
+    ///
+    /// # Example
+    /// ```
+    /// use lib_specbase::{SpecBase, Specfile, CURRENT_FORMAT_VERSION};
+    ///
+    /// let spec_db = SpecBase::init_in_memory().unwrap();
+    /// let id = spec_db
+    ///     .create_specfile(&Specfile {
+    ///         id: None,
+    ///         name: "Scratch".to_string(),
+    ///         description: String::new(),
+    ///         content: "Temporary notes".to_string(),
+    ///         format_version: CURRENT_FORMAT_VERSION,
+    ///         created_at: None,
+    ///         updated_at: None,
+    ///         tags: Vec::new(),
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(spec_db.read_specfile(id).unwrap().name, "Scratch");
+    /// ```
+    pub fn init_in_memory() -> Result<Self> {
+        SpecBaseBuilder::new().init_in_memory()
+    }
+
+    /// Returns the default database location, `specbase/specbase.db` in the
+    /// user's config directory (e.g. `~/.config/specbase/specbase.db` on Linux)
+    ///
+    /// # Returns
+    /// * `Ok(PathBuf)` - Path of the default database file
+    /// * `Err(Error)` - The user's config directory could not be determined
+    pub fn default_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().ok_or_else(|| SpecError::ConfigDirError {
+            path: PathBuf::from("~/.config/specbase"),
+            source: io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not determine the user's config directory",
+            ),
+        })?;
+        Ok(config_dir.join("specbase").join("specbase.db"))
+    }
+
+    /// Flushes pending writes and closes the database connection
+    ///
+    /// Checkpoints the write-ahead log (if the database uses one) with
+    /// `PRAGMA wal_checkpoint(TRUNCATE)` so all data lives in the main
+    /// database file, then closes the connection. Unlike dropping the
+    /// SpecBase, this reports errors, so callers can safely copy the file
+    /// afterwards, e.g. for backups.
+    ///
+    /// # Returns
+    /// * `Ok(())` - All data was written and the connection is closed
+    /// * `Err(Error)` - The checkpoint or the close failed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// spec_db.close().expect("Failed to close database");
+    /// ```
+    pub fn close(self) -> Result<()> {
+        if !self.read_only {
+            self.checkpoint(CheckpointMode::Truncate)?;
+        }
+        self.conn
+            .close()
+            .map_err(|(_, error)| SpecError::DatabaseError(error))?;
+        Ok(())
+    }
+
+    /// Returns a builder for opening the database with non-default options
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::builder().autoincrement(true).init().unwrap();
+    /// ```
+    pub fn builder() -> SpecBaseBuilder {
+        SpecBaseBuilder::new()
+    }
+}
+
+impl SpecBase {
+    /// Creates a new specfile in the database
+    ///
+    /// # Arguments
+    /// * `specfile` - The specfile to create. The `id` field will be ignored.
+    ///
+    /// # Returns
+    /// * `Ok(i64)` - ID of the newly created specfile
+    /// * `Err(Error)` - Failed to create specfile in database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, Specfile, CURRENT_FORMAT_VERSION};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let spec = Specfile {
+    ///     id: None,
+    ///     name: "Example".to_string(),
+    ///     description: "An example spec".to_string(),
+    ///     content: "# Example\nThis is an example.".to_string(),
+    ///     format_version: CURRENT_FORMAT_VERSION,
+    ///     created_at: None,
+    ///     updated_at: None,
+    ///     tags: Vec::new(),
+    /// };
+    ///
+    /// let id = spec_db.create_specfile(&spec).expect("Failed to create specfile");
+    /// ```
+    pub fn create_specfile(&self, specfile: &Specfile) -> Result<i64> {
+        self.check_writable()?;
+        self.check_name(&specfile.name)?;
+        let tx = self.conn.unchecked_transaction()?;
+        let id = insert_specfile(&tx, specfile)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Creates several specfiles in a single transaction
+    ///
+    /// Much faster than calling [`SpecBase::create_specfile`] in a loop,
+    /// which commits, and therefore syncs to disk, once per specfile. If any
+    /// insert fails, none of the specfiles are created.
+    ///
+    /// # Arguments
+    /// * `specfiles` - The specfiles to create; their IDs are ignored
+    ///
+    /// # Returns
+    /// * `Ok(Vec<i64>)` - The IDs of the new specfiles, in the order given
+    /// * `Err(Error)` - Failed to create a specfile; no changes were applied
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, Specfile, DEFAULT_TEMPLATE};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let specs: Vec<Specfile> = ["Auth", "Billing"]
+    ///     .into_iter()
+    ///     .map(|name| Specfile::scaffold(name, "", DEFAULT_TEMPLATE))
+    ///     .collect();
+    ///
+    /// let ids = spec_db.create_specfiles(&specs).unwrap();
+    /// ```
+    pub fn create_specfiles(&self, specfiles: &[Specfile]) -> Result<Vec<i64>> {
+        self.check_writable()?;
+        for specfile in specfiles {
+            self.check_name(&specfile.name)?;
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut ids = Vec::with_capacity(specfiles.len());
+        for specfile in specfiles {
+            ids.push(insert_specfile(&tx, specfile)?);
+        }
+        tx.commit()?;
+
+        Ok(ids)
+    }
+
+    /// Creates a copy of a specfile as a starting point for a similar one
+    ///
+    /// The copy is named "Copy of <name>" and has the same description,
+    /// content, format version, and tags. It gets its own slug and
+    /// timestamps; metadata is not copied.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to copy
+    ///
+    /// # Returns
+    /// * `Ok(i64)` - ID of the new specfile
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let copy = spec_db.clone_specfile(1).unwrap();
+    /// println!("Copied to ID {}", copy);
+    /// ```
+    pub fn clone_specfile(&self, id: i64) -> Result<i64> {
+        self.check_writable()?;
+        let tx = self.conn.unchecked_transaction()?;
+        let source = fetch_specfile(&tx, id)?;
+        let copy = Specfile {
+            name: format!("Copy of {}", source.name),
+            ..source
+        };
+        self.check_name(&copy.name)?;
+        let new_id = insert_specfile(&tx, &copy)?;
+        tx.commit()?;
+        Ok(new_id)
+    }
+
+    /// Splits the sections of a specfile at headings of a level into new specfiles
+    ///
+    /// Each heading of `level` that isn't nested in a block quote or list
+    /// starts a section, which runs up to the next heading of the same or a
+    /// higher level. Every section becomes a new specfile named after its
+    /// heading text, with the description, format version, and tags of the
+    /// original and a `spec://<id>` reference back to it at the end of the
+    /// content. The original is left unchanged unless `delete_original` is
+    /// set, in which case it is moved to the trash in the same transaction.
+    /// The reference is kept either way, so the parts still point at the
+    /// original once it is restored. If there are no headings of `level`,
+    /// nothing is created and the original is kept.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to split
+    /// * `level` - Heading level to split at, from 1 (`#`) to 6 (`######`)
+    /// * `delete_original` - Move the original to the trash after splitting it
+    ///
+    /// # Returns
+    /// * `Ok(Vec<i64>)` - IDs of the new specfiles in document order; empty if there are no headings of `level`
+    /// * `Err(SpecError::InvalidHeadingLevel)` - `level` is not between 1 and 6
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred; no specfiles were created
+    ///   and the original is unchanged
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let parts = spec_db.split_by_heading(1, 2, true).unwrap();
+    /// println!("Split into {} specfiles", parts.len());
+    /// ```
+    pub fn split_by_heading(&self, id: i64, level: u32, delete_original: bool) -> Result<Vec<i64>> {
+        self.check_writable()?;
+        if !(1..=6).contains(&level) {
+            return Err(SpecError::InvalidHeadingLevel(level).into());
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let source = fetch_specfile(&tx, id)?;
+        let mut ids = Vec::new();
+        for (index, section) in sections::split_at_headings(&source.content, level)
+            .into_iter()
+            .enumerate()
+        {
+            let name = if section.title.is_empty() {
+                format!("{} part {}", source.name, index + 1)
+            } else {
+                section.title
+            };
+            self.check_name(&name)?;
+            let part = Specfile {
+                id: None,
+                name,
+                description: source.description.clone(),
+                content: format!("{}\n\nSplit from spec://{}", section.text, id),
+                format_version: source.format_version,
+                created_at: None,
+                updated_at: None,
+                tags: source.tags.clone(),
+            };
+            ids.push(insert_specfile(&tx, &part)?);
+        }
+        if delete_original && !ids.is_empty() {
+            tx.execute(
+                &format!("UPDATE specfiles SET deleted_at = {NOW_RFC3339} WHERE id = ?1"),
+                params![id],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(ids)
+    }
+
+    /// Retrieves a specfile from the database by its ID
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to retrieve
+    ///
+    /// # Returns
+    /// * `Ok(Specfile)` - The requested specfile
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(SpecError::DatabaseError)` - The query failed for another reason
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// match spec_db.read_specfile(1) {
+    ///     Ok(spec) => println!("Found spec: {}", spec.name),
+    ///     Err(e) => eprintln!("Error: {}", e),
+    /// }
+    /// ```
+    pub fn read_specfile(&self, id: i64) -> Result<Specfile> {
+        let specfile = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE id = ?1 AND deleted_at IS NULL"
+                ),
+                params![id],
+                specfile_from_row,
+            )
+            .map_err(|error| match error {
+                rusqlite::Error::QueryReturnedNoRows => SpecError::SpecfileNotFound(id),
+                error => SpecError::DatabaseError(error),
+            })?;
+        Ok(specfile)
+    }
+
+    /// Retrieves a specfile by its ID, treating a missing row as a normal outcome
+    ///
+    /// Unlike [`SpecBase::read_specfile`], a specfile that doesn't exist is
+    /// not an error, so callers can tell an absent specfile apart from a
+    /// failing database.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to retrieve
+    ///
+    /// # Returns
+    /// * `Ok(Some(Specfile))` - The requested specfile
+    /// * `Ok(None)` - No specfile exists with the given ID
+    /// * `Err(Error)` - A database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// match spec_db.get_specfile(1).unwrap() {
+    ///     Some(spec) => println!("Found spec: {}", spec.name),
+    ///     None => println!("No spec with ID 1"),
+    /// }
+    /// ```
+    pub fn get_specfile(&self, id: i64) -> Result<Option<Specfile>> {
+        let specfile = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE id = ?1 AND deleted_at IS NULL"
+                ),
+                params![id],
+                specfile_from_row,
+            )
+            .optional()?;
+        Ok(specfile)
+    }
+
+    /// Checks whether a specfile exists without reading it
+    ///
+    /// Cheaper than [`SpecBase::get_specfile`] because no columns, in
+    /// particular not the content, are transferred.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to look for
+    ///
+    /// # Returns
+    /// * `Ok(true)` - A specfile exists with the given ID
+    /// * `Ok(false)` - No specfile exists with the given ID
+    /// * `Err(Error)` - A database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// if !spec_db.specfile_exists(1).unwrap() {
+    ///     println!("No spec with ID 1");
+    /// }
+    /// ```
+    pub fn specfile_exists(&self, id: i64) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM specfiles WHERE id = ?1 AND deleted_at IS NULL LIMIT 1",
+                params![id],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Retrieves a specfile by its slug
+    ///
+    /// Every specfile gets a unique, URL-friendly slug derived from its name
+    /// when it is created: lowercased, with spaces turned into hyphens and
+    /// punctuation removed, e.g. `"Rate Limits (v2)"` becomes `rate-limits-v2`.
+    /// If another specfile already uses that slug, a numeric suffix such as
+    /// `-2` is appended. The slug stays the same until the name changes.
+    ///
+    /// # Arguments
+    /// * `slug` - The slug of the specfile to retrieve
+    ///
+    /// # Returns
+    /// * `Ok(Specfile)` - The requested specfile
+    /// * `Err(SpecError::SlugNotFound)` - No specfile has the given slug
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let spec = spec_db.read_by_slug("rate-limits").unwrap();
+    /// println!("{}", spec.content);
+    /// ```
+    pub fn read_by_slug(&self, slug: &str) -> Result<Specfile> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE slug = ?1 AND deleted_at IS NULL"
+                ),
+                params![slug],
+                specfile_from_row,
+            )
+            .optional()?
+            .ok_or_else(|| SpecError::SlugNotFound(slug.to_string()).into())
+    }
+
+    /// Returns the slug of a specfile
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile
+    ///
+    /// # Returns
+    /// * `Ok(Some(String))` - The specfile's slug
+    /// * `Ok(None)` - The specfile predates slugs and has none assigned yet
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    pub fn slug(&self, id: i64) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT slug FROM specfiles WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| SpecError::SpecfileNotFound(id).into())
+    }
+
+    /// Updates an existing specfile in the database
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to update
+    /// * `specfile` - The new specfile data. The `id` field will be ignored.
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully updated the specfile
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, Specfile, CURRENT_FORMAT_VERSION};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let updated_spec = Specfile {
+    ///     id: Some(1),
+    ///     name: "Updated Example".to_string(),
+    ///     description: "Updated description".to_string(),
+    ///     content: "# Updated\nThis spec has been updated.".to_string(),
+    ///     format_version: CURRENT_FORMAT_VERSION,
+    ///     created_at: None,
+    ///     updated_at: None,
+    ///     tags: Vec::new(),
+    /// };
+    ///
+    /// match spec_db.update_specfile(1, &updated_spec) {
+    ///     Ok(_) => println!("Successfully updated specfile"),
+    ///     Err(e) => eprintln!("Error: {}", e),
+    /// }
+    /// ```
+    pub fn update_specfile(&self, id: i64, specfile: &Specfile) -> Result<()> {
+        self.check_writable()?;
+        self.check_name(&specfile.name)?;
+        let tx = self.conn.unchecked_transaction()?;
+        let rows_affected = write_specfile(&tx, id, specfile)?;
+
+        if rows_affected == 0 {
+            return Err(SpecError::SpecfileNotFound(id).into());
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Updates an existing specfile and returns its previous state
+    ///
+    /// Works like [`SpecBase::update_specfile`], but reads the specfile in the
+    /// same transaction before changing it, e.g. to offer an undo.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to update
+    /// * `specfile` - The new specfile data. The `id` field will be ignored.
+    ///
+    /// # Returns
+    /// * `Ok(Specfile)` - The specfile as it was before the update
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, Specfile};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let edited = Specfile {
+    ///     description: "Reviewed".to_string(),
+    ///     ..spec_db.read_specfile(1).unwrap()
+    /// };
+    /// let previous = spec_db.update_specfile_returning(1, &edited).unwrap();
+    /// println!("Description was: {}", previous.description);
+    /// ```
+    pub fn update_specfile_returning(&self, id: i64, specfile: &Specfile) -> Result<Specfile> {
+        self.check_writable()?;
+        self.check_name(&specfile.name)?;
+        let tx = self.conn.unchecked_transaction()?;
+        let previous = fetch_specfile(&tx, id)?;
+        write_specfile(&tx, id, specfile)?;
+        tx.commit()?;
+        Ok(previous)
+    }
+
+    /// Updates only the given fields of a specfile
+    ///
+    /// Unlike [`SpecBase::update_specfile`], the caller doesn't need to read
+    /// the specfile first: fields passed as `None` keep their current value,
+    /// and the `UPDATE` only writes the columns that change. A new name also
+    /// gets a new slug. If all fields are `None`, nothing is written and
+    /// `Ok(())` is returned without checking that the specfile exists.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to update
+    /// * `name` - New name, or `None` to keep the current one
+    /// * `description` - New description, or `None` to keep the current one
+    /// * `content` - New content, or `None` to keep the current one
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully updated the specfile, or nothing to update
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// spec_db
+    ///     .update_specfile_fields(1, None, Some("Reviewed"), None)
+    ///     .unwrap();
+    /// ```
+    pub fn update_specfile_fields(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        description: Option<&str>,
+        content: Option<&str>,
+    ) -> Result<()> {
+        self.check_writable()?;
+        if name.is_none() && description.is_none() && content.is_none() {
+            return Ok(());
+        }
+        if let Some(name) = name {
+            self.check_name(name)?;
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let slug = match name {
+            Some(name) => Some(allocate_slug(&tx, name, Some(id))?),
+            None => None,
+        };
+        let fields = [
+            ("name", name),
+            ("description", description),
+            ("content", content),
+            ("slug", slug.as_deref()),
+        ];
+
+        let mut assignments = Vec::new();
+        let mut values: Vec<&dyn rusqlite::ToSql> = Vec::new();
+        for (column, value) in &fields {
+            if let Some(value) = value {
+                values.push(value);
+                assignments.push(format!("{} = ?{}", column, values.len()));
+            }
+        }
+        values.push(&id);
+        let sql = format!(
+            "UPDATE specfiles SET {}, updated_at = {NOW_RFC3339} WHERE id = ?{} AND deleted_at IS NULL",
+            assignments.join(", "),
+            values.len()
+        );
+
+        let rows_affected = tx
+            .execute(&sql, params_from_iter(values))
+            .map_err(|error| name_conflict(error, name.unwrap_or_default()))?;
+        if rows_affected == 0 {
+            return Err(SpecError::SpecfileNotFound(id).into());
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Updates a specfile only if nobody changed it since it was read
+    ///
+    /// Implements optimistic concurrency: the caller passes the
+    /// [`Specfile::version`] of the specfile it read, and the update is only
+    /// applied if the stored specfile still has that version. Otherwise
+    /// someone else wrote to it in the meantime and the update is rejected
+    /// instead of silently overwriting their change. The check and the write
+    /// happen in one transaction.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to update
+    /// * `specfile` - The new specfile data. The `id` field will be ignored.
+    /// * `expected_version` - Version of the specfile when it was read
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully updated the specfile
+    /// * `Err(SpecError::ConflictDetected)` - The specfile changed since it was read
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, Specfile};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let original = spec_db.read_specfile(1).unwrap();
+    /// let edited = Specfile {
+    ///     content: format!("{}\nMore details.", original.content),
+    ///     ..original.clone()
+    /// };
+    ///
+    /// match spec_db.update_specfile_checked(1, &edited, original.version()) {
+    ///     Ok(_) => println!("Saved"),
+    ///     Err(e) => eprintln!("Not saved: {}", e),
+    /// }
+    /// ```
+    pub fn update_specfile_checked(
+        &self,
+        id: i64,
+        specfile: &Specfile,
+        expected_version: u64,
+    ) -> Result<()> {
+        self.check_writable()?;
+        self.check_name(&specfile.name)?;
+        let tx = self.conn.unchecked_transaction()?;
+        let current = fetch_specfile(&tx, id)?;
+        if current.version() != expected_version {
+            return Err(SpecError::ConflictDetected { id }.into());
+        }
+
+        write_specfile(&tx, id, specfile)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Moves a specfile to the trash
+    ///
+    /// The specfile is hidden from reads, listings, and queries, but keeps
+    /// its content and tags until [`SpecBase::purge_deleted`] removes it.
+    /// Use [`SpecBase::restore_specfile`] to bring it back.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to delete
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully moved the specfile to the trash
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// match spec_db.delete_specfile(1) {
+    ///     Ok(_) => println!("Successfully deleted specfile"),
+    ///     Err(e) => eprintln!("Error: {}", e),
+    /// }
+    /// ```
+    pub fn delete_specfile(&self, id: i64) -> Result<()> {
+        self.delete_specfile_returning(id)?;
+        Ok(())
+    }
+
+    /// Moves a specfile to the trash and returns it
+    ///
+    /// Works like [`SpecBase::delete_specfile`], but reads the specfile in the
+    /// same transaction before deleting it, e.g. to show what was deleted.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to delete
+    ///
+    /// # Returns
+    /// * `Ok(Specfile)` - The deleted specfile
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let deleted = spec_db.delete_specfile_returning(1).unwrap();
+    /// println!("Deleted {}", deleted.name);
+    /// ```
+    pub fn delete_specfile_returning(&self, id: i64) -> Result<Specfile> {
+        self.check_writable()?;
+        let tx = self.conn.unchecked_transaction()?;
+        let deleted = fetch_specfile(&tx, id)?;
+        let snapshot = snapshot_rows(&tx, &[id])?;
+        tx.execute(
+            &format!("UPDATE specfiles SET deleted_at = {NOW_RFC3339} WHERE id = ?1"),
+            params![id],
+        )?;
+        record_undo(&tx, &snapshot)?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Restores a specfile from the trash
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the deleted specfile
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully restored the specfile
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile with the given ID is in the trash
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// spec_db.delete_specfile(1).unwrap();
+    /// spec_db.restore_specfile(1).unwrap();
+    /// ```
+    pub fn restore_specfile(&self, id: i64) -> Result<()> {
+        self.check_writable()?;
+        let name: String = self
+            .conn
+            .query_row(
+                "SELECT name FROM specfiles WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SpecError::SpecfileNotFound(id))?;
+        self.conn
+            .execute(
+                "UPDATE specfiles SET deleted_at = NULL WHERE id = ?1",
+                params![id],
+            )
+            .map_err(|error| name_conflict(error, &name))?;
+        Ok(())
+    }
+
+    /// Lists the specfiles in the trash, with the time each was deleted
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(Specfile, String)>)` - Deleted specfiles and their RFC 3339 deletion times, oldest deletion first
+    /// * `Err(Error)` - Database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (specfile, deleted_at) in spec_db.list_deleted().unwrap() {
+    ///     println!("{} deleted at {}", specfile.name, deleted_at);
+    /// }
+    /// ```
+    pub fn list_deleted(&self) -> Result<Vec<(Specfile, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SPECFILE_COLUMNS}, deleted_at FROM specfiles
+             WHERE deleted_at IS NOT NULL
+             ORDER BY deleted_at, id"
+        ))?;
+
+        let deleted = stmt
+            .query_map([], |row| Ok((specfile_from_row(row)?, row.get(8)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(deleted)
+    }
+
+    /// Permanently removes all specfiles in the trash
+    ///
+    /// Tags no longer used by any specfile are removed as well.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - Number of specfiles removed
+    /// * `Err(Error)` - Database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let purged = spec_db.purge_deleted().unwrap();
+    /// println!("Removed {} specfiles", purged);
+    /// ```
+    pub fn purge_deleted(&self) -> Result<usize> {
+        self.check_writable()?;
+        let tx = self.conn.unchecked_transaction()?;
+        let ids = tx
+            .prepare("SELECT id FROM specfiles WHERE deleted_at IS NOT NULL ORDER BY id")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let snapshot = snapshot_rows(&tx, &ids)?;
+
+        tx.execute(
+            "DELETE FROM specfile_tags WHERE specfile_id IN (
+                 SELECT id FROM specfiles WHERE deleted_at IS NOT NULL
+             )",
+            [],
+        )?;
+        tx.execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM specfile_tags)",
+            [],
+        )?;
+        let purged = tx.execute("DELETE FROM specfiles WHERE deleted_at IS NOT NULL", [])?;
+        record_undo(&tx, &snapshot)?;
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    /// Reverses the most recent delete, purge, or bulk update
+    ///
+    /// [`SpecBase::delete_specfile`], [`SpecBase::delete_specfile_returning`],
+    /// [`SpecBase::purge_deleted`], and [`SpecBase::patch_many`] save the
+    /// specfiles they change, including content, tags, timestamps, and
+    /// metadata, to a journal in the database. Undoing writes them back.
+    ///
+    /// Only the single most recent of these operations can be undone, and
+    /// only once. Any later change to a specfile, by this or another process,
+    /// discards the journal, so an undo never overwrites newer edits.
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully reversed the operation
+    /// * `Err(SpecError::NothingToUndo)` - No operation is recorded, or it was discarded
+    /// * `Err(Error)` - Other database error occurred
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// spec_db.delete_specfile(1).unwrap();
+    /// spec_db.undo_last().unwrap();
+    /// assert!(spec_db.specfile_exists(1).unwrap());
+    /// ```
+    pub fn undo_last(&self) -> Result<()> {
+        self.check_writable()?;
+        let tx = self.conn.unchecked_transaction()?;
+        let snapshot: String = tx
+            .query_row("SELECT snapshot_json FROM undo_journal", [], |row| {
+                row.get(0)
+            })
+            .optional()?
+            .ok_or(SpecError::NothingToUndo)?;
+        let rows: Vec<UndoRow> = serde_json::from_str(&snapshot)?;
+
+        // Writing the rows back discards the journal through its triggers
+        for row in &rows {
+            tx.execute(
+                "INSERT INTO specfiles (
+                     id, name, description, content, slug, format_version,
+                     created_at, updated_at, metadata_json, deleted_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT (id) DO UPDATE SET
+                     name = excluded.name,
+                     description = excluded.description,
+                     content = excluded.content,
+                     slug = excluded.slug,
+                     format_version = excluded.format_version,
+                     created_at = excluded.created_at,
+                     updated_at = excluded.updated_at,
+                     metadata_json = excluded.metadata_json,
+                     deleted_at = excluded.deleted_at",
+                params![
+                    row.id,
+                    row.name,
+                    row.description,
+                    row.content,
+                    row.slug,
+                    row.format_version,
+                    row.created_at,
+                    row.updated_at,
+                    row.metadata_json,
+                    row.deleted_at
+                ],
+            )
+            .map_err(|error| name_conflict(error, &row.name))?;
+            set_tags(&tx, row.id, &row.tags)?;
+        }
+        tx.execute("DELETE FROM undo_journal", [])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns [`SpecError::ReadOnly`] if the database was opened immutable
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(SpecError::ReadOnly.into());
+        }
+        Ok(())
+    }
+
+    /// Returns an error if `name` doesn't match the configured naming convention
+    fn check_name(&self, name: &str) -> Result<()> {
+        match &self.name_pattern {
+            Some(pattern) if !pattern.regex.is_match(name) => {
+                Err(SpecError::NameConventionViolation {
+                    name: name.to_string(),
+                    pattern: pattern.source.clone(),
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs a search or listing under the configured query timeout
+    ///
+    /// Nested calls share the deadline of the outermost one. Errors caused
+    /// by the progress handler aborting a query become
+    /// [`SpecError::QueryTimeout`].
+    fn with_query_timeout<T>(&self, operation: impl FnOnce() -> Result<T>) -> Result<T> {
+        let Some(timeout) = &self.query_timeout else {
+            return operation();
+        };
+        let armed = match timeout.deadline.lock() {
+            Ok(mut deadline) if deadline.is_none() => {
+                *deadline = Some(Instant::now() + timeout.limit);
+                true
+            }
+            _ => false,
+        };
+
+        let result = operation();
+
+        if armed {
+            if let Ok(mut deadline) = timeout.deadline.lock() {
+                *deadline = None;
+            }
+        }
+        result.map_err(|error| {
+            let interrupted = error.chain().any(|cause| {
+                matches!(
+                    cause.downcast_ref::<rusqlite::Error>(),
+                    Some(rusqlite::Error::SqliteFailure(failure, _))
+                        if failure.code == rusqlite::ErrorCode::OperationInterrupted
+                )
+            });
+            if interrupted {
+                SpecError::QueryTimeout(timeout.limit).into()
+            } else {
+                error
+            }
+        })
+    }
+}
+
+impl SpecBase {
+    /// Applies the same partial update to several specfiles at once
+    ///
+    /// All updates run in a single transaction: either every listed specfile
+    /// is patched or, if a database error occurs, none are. IDs that don't
+    /// exist are skipped and not counted. An empty patch changes nothing
+    /// and returns `Ok(0)`.
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs of the specfiles to update
+    /// * `patch` - The fields to change on each specfile
+    ///
+    /// # Returns
+    /// * `Ok(u64)` - Number of specfiles that were updated
+    /// * `Err(Error)` - Failed to update the database; no changes were applied
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, SpecfilePatch};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let patch = SpecfilePatch {
+    ///     description: Some("Reviewed".to_string()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let updated = spec_db.patch_many(&[1, 2, 3], patch).unwrap();
+    /// println!("Updated {} specfiles", updated);
+    /// ```
+    pub fn patch_many(&self, ids: &[i64], patch: SpecfilePatch) -> Result<u64> {
+        self.check_writable()?;
+        if patch.is_empty() || ids.is_empty() {
+            return Ok(0);
+        }
+        if let Some(name) = &patch.name {
+            self.check_name(name)?;
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let snapshot = snapshot_rows(&tx, ids)?;
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "UPDATE specfiles SET
+                    name = COALESCE(?1, name),
+                    description = COALESCE(?2, description),
+                    content = COALESCE(?3, content),
+                    slug = COALESCE(?4, slug),
+                    updated_at = {NOW_RFC3339}
+                 WHERE id = ?5 AND deleted_at IS NULL"
+            ))?;
+            for id in ids {
+                let slug = match &patch.name {
+                    Some(name) => Some(allocate_slug(&tx, name, Some(*id))?),
+                    None => None,
+                };
+                updated += stmt
+                    .execute(params![
+                        patch.name,
+                        patch.description,
+                        patch.content,
+                        slug,
+                        id
+                    ])
+                    .map_err(|error| {
+                        name_conflict(error, patch.name.as_deref().unwrap_or_default())
+                    })? as u64;
+            }
+        }
+        if updated > 0 {
+            record_undo(&tx, &snapshot)?;
+        }
+        tx.commit()?;
+
+        Ok(updated)
+    }
+}
+
+impl SpecBase {
+    /// Attaches arbitrary JSON metadata to a specfile, replacing any previous value
+    ///
+    /// Use this for structured data that doesn't fit into the name or
+    /// description, e.g. the payload of a linked ticket. Metadata is stored
+    /// as JSON text next to the specfile and is not part of [`Specfile`], so
+    /// listing specfiles doesn't load it. Setting [`serde_json::Value::Null`]
+    /// removes the metadata. The column also rejects malformed JSON written
+    /// with raw SQL.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile
+    /// * `value` - The metadata to store
+    ///
+    /// # Returns
+    /// * `Ok(())` - Metadata stored
+    /// * `Err(Error)` - Specfile not found or database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    /// use serde_json::json;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let ticket = json!({ "key": "API-42", "status": "open", "labels": ["auth"] });
+    /// spec_db.set_metadata_json(1, &ticket).unwrap();
+    /// ```
+    pub fn set_metadata_json(&self, id: i64, value: &serde_json::Value) -> Result<()> {
+        self.check_writable()?;
+        let text = match value {
+            serde_json::Value::Null => None,
+            value => Some(serde_json::to_string(value)?),
+        };
+        let rows_affected = self.conn.execute(
+            "UPDATE specfiles SET metadata_json = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![text, id],
+        )?;
+
+        if rows_affected == 0 {
+            return Err(SpecError::SpecfileNotFound(id).into());
+        }
+        Ok(())
+    }
+
+    /// Retrieves the JSON metadata attached to a specfile
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile
+    ///
+    /// # Returns
+    /// * `Ok(Some(Value))` - The stored metadata
+    /// * `Ok(None)` - The specfile has no metadata
+    /// * `Err(Error)` - Specfile not found or database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// if let Some(metadata) = spec_db.get_metadata_json(1).unwrap() {
+    ///     println!("Ticket status: {}", metadata["status"]);
+    /// }
+    /// ```
+    pub fn get_metadata_json(&self, id: i64) -> Result<Option<serde_json::Value>> {
+        let text: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT metadata_json FROM specfiles WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SpecError::SpecfileNotFound(id))?;
+
+        match text {
+            Some(text) => Ok(Some(serde_json::from_str(&text).with_context(|| {
+                format!("Metadata of specfile {} is not valid JSON", id)
+            })?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl SpecBase {
+    /// Recomputes the slugs of all specfiles from their current names
+    ///
+    /// Use this to assign slugs to specfiles created before slugs existed or
+    /// after the slug rules change. Specfiles are processed in ascending ID
+    /// order, so when several names map to the same slug the oldest specfile
+    /// gets the plain slug and later ones get `-2`, `-3`, and so on. Running
+    /// it twice in a row changes nothing the second time. All changes are
+    /// written in a single transaction.
+    ///
+    /// # Returns
+    /// * `Ok(u64)` - Number of specfiles whose slug changed
+    /// * `Err(Error)` - Failed to update the database; no slugs were changed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let changed = spec_db.regenerate_slugs().unwrap();
+    /// println!("Updated {} slugs", changed);
+    /// ```
+    pub fn regenerate_slugs(&self) -> Result<u64> {
+        self.check_writable()?;
+        let tx = self.conn.unchecked_transaction()?;
+
+        let mut stmt = tx.prepare("SELECT id, name, slug FROM specfiles ORDER BY id")?;
+        let specfiles = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut assigned = HashSet::with_capacity(specfiles.len());
+        let mut changes = Vec::new();
+        for (id, name, current) in specfiles {
+            let slug = slug::first_free(&slug::base_slug(&name), |candidate| {
+                assigned.contains(candidate)
+            });
+            assigned.insert(slug.clone());
+            if current.as_deref() != Some(slug.as_str()) {
+                changes.push((id, slug));
+            }
+        }
+
+        // Clear changed slugs first so swaps don't trip the unique index
+        {
+            let mut clear = tx.prepare("UPDATE specfiles SET slug = NULL WHERE id = ?1")?;
+            for (id, _) in &changes {
+                clear.execute(params![id])?;
+            }
+            let mut set = tx.prepare("UPDATE specfiles SET slug = ?1 WHERE id = ?2")?;
+            for (id, slug) in &changes {
+                set.execute(params![slug, id])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(changes.len() as u64)
+    }
+
+    /// Lists all specfiles in the database
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - List of all specfiles
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// match spec_db.list_specfiles() {
+    ///     Ok(specs) => {
+    ///         for spec in specs {
+    ///             println!("Found spec: {} (ID: {})", spec.name, spec.id.unwrap());
+    ///         }
+    ///     },
+    ///     Err(e) => eprintln!("Error: {}", e),
+    /// }
+    /// ```
+    pub fn list_specfiles(&self) -> Result<Vec<Specfile>> {
+        self.with_query_timeout(|| {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE deleted_at IS NULL"
+            ))?;
+
+            let specfiles = stmt
+                .query_map([], specfile_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(specfiles)
+        })
+    }
+
+    /// Lists all specfiles keyed by their ID
+    ///
+    /// Convenient for lookups by ID. Serialized with `serde_json`, the map
+    /// becomes an object with the IDs as string keys, e.g. `{"5": {...}}`.
+    ///
+    /// # Returns
+    /// * `Ok(BTreeMap<i64, Specfile>)` - All specfiles, ordered by ID
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let specs = spec_db.list_as_map().unwrap();
+    /// if let Some(spec) = specs.get(&5) {
+    ///     println!("Found spec: {}", spec.name);
+    /// }
+    /// ```
+    pub fn list_as_map(&self) -> Result<BTreeMap<i64, Specfile>> {
+        let specfiles = self.list_specfiles()?;
+        Ok(specfiles
+            .into_iter()
+            .filter_map(|specfile| Some((specfile.id?, specfile)))
+            .collect())
+    }
+
+    /// Lists one page of specfiles, ordered by ID
+    ///
+    /// The order is stable, so stepping `offset` by `limit` visits every
+    /// specfile exactly once as long as no specfiles are added or deleted
+    /// in between.
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of specfiles to return
+    /// * `offset` - Number of specfiles to skip
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - The specfiles of the page; fewer than `limit` on the last page
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let second_page = spec_db.list_specfiles_paged(50, 50).unwrap();
+    /// ```
+    pub fn list_specfiles_paged(&self, limit: u32, offset: u32) -> Result<Vec<Specfile>> {
+        self.with_query_timeout(|| {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE deleted_at IS NULL
+                 ORDER BY id LIMIT ?1 OFFSET ?2"
+            ))?;
+
+            let specfiles = stmt
+                .query_map(params![limit, offset], specfile_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(specfiles)
+        })
+    }
+
+    /// Iterates over all specfiles, ordered by ID, without loading their content
+    ///
+    /// All metadata is read up front, which is cheap even for large
+    /// databases; content is read one specfile at a time with
+    /// [`SpecSummary::load_content`], so only the content actually needed is
+    /// held in memory.
+    ///
+    /// # Returns
+    /// * `Ok(SpecfileCursor)` - Iterator over the specfile summaries
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for summary in spec_db.cursor().unwrap() {
+    ///     if summary.tags.iter().any(|tag| tag == "auth") {
+    ///         println!("{}", summary.load_content().unwrap());
+    ///     }
+    /// }
+    /// ```
+    pub fn cursor(&self) -> Result<SpecfileCursor<'_>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SUMMARY_COLUMNS} FROM specfiles WHERE deleted_at IS NULL ORDER BY id"
+        ))?;
+
+        let summaries = stmt
+            .query_map([], |row| {
+                Ok(SpecSummary {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    format_version: row.get(3)?,
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    tags: tags_from_row(row, 6)?,
+                    spec_db: self,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SpecfileCursor {
+            summaries: summaries.into_iter(),
+        })
+    }
+
+    /// Returns one page of filtered and sorted specfile summaries with the total count
+    ///
+    /// This combines searching, tag filtering, sorting, and pagination in a
+    /// single SQL statement, which also counts all matching specfiles, so a
+    /// web endpoint needs only one call per request. Summaries leave out the
+    /// content to keep the result small; use [`SpecSummary::load_content`]
+    /// where it's needed.
+    ///
+    /// # Arguments
+    /// * `params` - Filters, sort order, and page to return
+    ///
+    /// # Returns
+    /// * `Ok(PagedResult<SpecSummary>)` - The page and the total number of matches;
+    ///   no items if the page is past the last one
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{QueryParams, SortDirection, SortKey, SpecBase};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let result = spec_db
+    ///     .query_page(QueryParams {
+    ///         query: Some("rate limit".to_string()),
+    ///         tags: vec!["api".to_string()],
+    ///         sort: SortKey::UpdatedAt,
+    ///         direction: SortDirection::Descending,
+    ///         per_page: 20,
+    ///         ..QueryParams::default()
+    ///     })
+    ///     .unwrap();
+    /// println!("page 1 of {} ({} matches)", result.page_count(), result.total);
+    /// for summary in &result.items {
+    ///     println!("- {} (ID: {})", summary.name, summary.id);
+    /// }
+    /// ```
+    pub fn query_page(&self, params: QueryParams) -> Result<PagedResult<SpecSummary<'_>>> {
+        let page = params.page.max(1);
+        let per_page = params.per_page.max(1);
+
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(query) = &params.query {
+            let (condition, patterns) = search_condition(query, params.mode);
+            conditions.push(format!("({})", condition));
+            values.extend(patterns.into_iter().map(Value::Text));
+        }
+        for tag in &params.tags {
+            values.push(Value::Text(tag.clone()));
+            conditions.push(format!(
+                "id IN (SELECT specfile_tags.specfile_id
+                        FROM specfile_tags JOIN tags ON tags.id = specfile_tags.tag_id
+                        WHERE tags.name = ?{})",
+                values.len()
+            ));
+        }
+        values.push(Value::Integer(i64::from(per_page)));
+        values.push(Value::Integer(i64::from(page - 1) * i64::from(per_page)));
+
+        let direction = params.direction.as_sql();
+        let order_by = |table: &str| {
+            format!(
+                "{table}{key} {direction}, {table}id {direction}",
+                key = params.sort.as_sql()
+            )
+        };
+        // The page is left-joined to the count, so the total is returned
+        // even if the page is past the last one and has no rows
+        let sql = format!(
+            "WITH matches AS (SELECT id FROM specfiles WHERE {conditions})
+             SELECT total.n, page.*
+             FROM (SELECT COUNT(*) AS n FROM matches) AS total
+             LEFT JOIN (
+                 SELECT {SUMMARY_COLUMNS} FROM specfiles
+                 WHERE id IN matches
+                 ORDER BY {inner_order}
+                 LIMIT ?{limit} OFFSET ?{offset}
+             ) AS page
+             ORDER BY {outer_order}",
+            conditions = conditions.join(" AND "),
+            inner_order = order_by(""),
+            outer_order = order_by("page."),
+            limit = values.len() - 1,
+            offset = values.len(),
+        );
+
+        self.with_query_timeout(|| {
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(params_from_iter(&values))?;
+
+            let mut total = 0;
+            let mut items = Vec::new();
+            while let Some(row) = rows.next()? {
+                total = row.get(0)?;
+                let Some(id) = row.get::<_, Option<i64>>(1)? else {
+                    continue;
+                };
+                items.push(SpecSummary {
+                    id,
+                    name: row.get(2)?,
+                    description: row.get(3)?,
+                    format_version: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    tags: tags_from_row(row, 7)?,
+                    spec_db: self,
+                });
+            }
+
+            Ok(PagedResult {
+                items,
+                total,
+                page,
+                per_page,
+            })
+        })
+    }
+
+    /// Counts the specfiles in the database without loading them
+    ///
+    /// # Returns
+    /// * `Ok(u64)` - Number of specfiles
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// println!("{} specs", spec_db.count_specfiles().unwrap());
+    /// ```
+    pub fn count_specfiles(&self) -> Result<u64> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM specfiles WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Lists the specfiles that have a tag
+    ///
+    /// # Arguments
+    /// * `tag` - The tag to look for; must match exactly, including case
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - Specfiles with the tag, ordered by ID
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for spec in spec_db.list_specfiles_by_tag("auth").unwrap() {
+    ///     println!("{}: {}", spec.id.unwrap(), spec.name);
+    /// }
+    /// ```
+    pub fn list_specfiles_by_tag(&self, tag: &str) -> Result<Vec<Specfile>> {
+        self.with_query_timeout(|| {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles
+                 WHERE deleted_at IS NULL AND id IN (
+                     SELECT specfile_tags.specfile_id
+                     FROM specfile_tags JOIN tags ON tags.id = specfile_tags.tag_id
+                     WHERE tags.name = ?1
+                 )
+                 ORDER BY id"
+            ))?;
+
+            let specfiles = stmt
+                .query_map(params![tag.trim()], specfile_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(specfiles)
+        })
+    }
+
+    /// Lists the specfiles that share tags with a specfile, most shared tags first
+    ///
+    /// Useful for "related specs" suggestions without explicit links.
+    /// Specfiles sharing the same number of tags are ordered by ID. The
+    /// specfile itself is never included.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to find related specfiles for
+    /// * `limit` - Maximum number of specfiles to return
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(Specfile, u64)>)` - Related specfiles and the number of tags each shares
+    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (spec, shared) in spec_db.related_by_tags(1, 5).unwrap() {
+    ///     println!("{} ({} shared tags)", spec.name, shared);
+    /// }
+    /// ```
+    pub fn related_by_tags(&self, id: i64, limit: u32) -> Result<Vec<(Specfile, u64)>> {
+        self.with_query_timeout(|| {
+            if !self.specfile_exists(id)? {
+                return Err(SpecError::SpecfileNotFound(id).into());
+            }
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS}, related.shared FROM specfiles
+                 JOIN (
+                     SELECT other.specfile_id AS specfile_id, COUNT(*) AS shared
+                     FROM specfile_tags AS own
+                     JOIN specfile_tags AS other ON other.tag_id = own.tag_id
+                     WHERE own.specfile_id = ?1 AND other.specfile_id != ?1
+                     GROUP BY other.specfile_id
+                 ) AS related ON related.specfile_id = specfiles.id
+                 WHERE specfiles.deleted_at IS NULL
+                 ORDER BY related.shared DESC, specfiles.id
+                 LIMIT ?2"
+            ))?;
+
+            let related = stmt
+                .query_map(params![id, limit], |row| {
+                    Ok((specfile_from_row(row)?, row.get(8)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(related)
+        })
+    }
+
+    /// Searches for specfiles using a fulltext query
+    ///
+    /// Searches through the name, description, and content of all specfiles
+    /// for matches with the given query string. The search is case-insensitive
+    /// and uses SQL LIKE with wildcards, so it also finds the query inside
+    /// words, but it scans every row and returns matches unranked. Use
+    /// [`SpecBase::search_specfiles`] for indexed, ranked search by words.
+    ///
+    /// # Arguments
+    /// * `query` - The search term to look for
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - List of matching specfiles
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// match spec_db.query_specfiles("example") {
+    ///     Ok(specs) => {
+    ///         println!("Found {} matching specs:", specs.len());
+    ///         for spec in specs {
+    ///             println!("- {} (ID: {})", spec.name, spec.id.unwrap());
+    ///         }
+    ///     },
+    ///     Err(e) => eprintln!("Error: {}", e),
+    /// }
+    /// ```
+    pub fn query_specfiles(&self, query: &str) -> Result<Vec<Specfile>> {
+        self.query_specfiles_with_mode(query, QueryMode::Phrase)
+    }
+
+    /// Searches for specfiles, matching a multi-word query as a phrase or word by word
+    ///
+    /// See [`QueryMode`] for how each mode is translated to SQL.
+    ///
+    /// # Arguments
+    /// * `query` - The search terms to look for
+    /// * `mode` - Whether to match the query as a phrase, or require all or any of its words
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - List of matching specfiles
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{QueryMode, SpecBase};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for spec in spec_db.query_specfiles_with_mode("cache eviction", QueryMode::All).unwrap() {
+    ///     println!("- {} (ID: {})", spec.name, spec.id.unwrap());
+    /// }
+    /// ```
+    pub fn query_specfiles_with_mode(&self, query: &str, mode: QueryMode) -> Result<Vec<Specfile>> {
+        self.with_query_timeout(|| {
+            let (condition, patterns) = search_condition(query, mode);
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE deleted_at IS NULL AND ({})",
+                condition
+            ))?;
+
+            let specfiles = stmt
+                .query_map(params_from_iter(patterns), specfile_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(specfiles)
+        })
+    }
+
+    /// Searches specfiles by words using the full-text index, best matches first
+    ///
+    /// Unlike [`SpecBase::query_specfiles`], this matches whole words
+    /// (case-insensitive) rather than substrings: every word of the query
+    /// must appear in the name, description, or content, in any order.
+    /// Results are ranked by SQLite's `bm25()` relevance, which favors
+    /// specfiles where the words are frequent and the text is short.
+    /// Punctuation in the query only separates words; FTS5 query syntax is
+    /// not interpreted.
+    ///
+    /// # Arguments
+    /// * `query` - The words to look for
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - Matching specfiles, most relevant first
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for spec in spec_db.search_specfiles("cache eviction").unwrap() {
+    ///     println!("- {} (ID: {})", spec.name, spec.id.unwrap());
+    /// }
+    /// ```
+    pub fn search_specfiles(&self, query: &str) -> Result<Vec<Specfile>> {
+        self.with_query_timeout(|| {
+            let Some(fts_query) = fts_query(query) else {
+                return Ok(Vec::new());
+            };
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles
+                 JOIN (
+                     SELECT rowid, bm25(specfiles_fts) AS rank
+                     FROM specfiles_fts WHERE specfiles_fts MATCH ?1
+                 ) AS hits ON hits.rowid = specfiles.id
+                 WHERE specfiles.deleted_at IS NULL
+                 ORDER BY hits.rank, specfiles.id"
+            ))?;
+
+            let specfiles = stmt
+                .query_map(params![fts_query], specfile_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(specfiles)
+        })
+    }
+
+    /// Returns the IDs of specfiles matching a fulltext query
+    ///
+    /// Matches exactly like [`SpecBase::query_specfiles`] but only fetches the
+    /// IDs, which avoids loading content when the caller just needs to act on
+    /// the matches.
+    ///
+    /// # Arguments
+    /// * `query` - The search term to look for
+    ///
+    /// # Returns
+    /// * `Ok(Vec<i64>)` - IDs of matching specfiles in ascending order
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for id in spec_db.query_ids("example").unwrap() {
+    ///     println!("{}", id);
+    /// }
+    /// ```
+    pub fn query_ids(&self, query: &str) -> Result<Vec<i64>> {
+        self.query_ids_with_mode(query, QueryMode::Phrase)
+    }
+
+    /// Returns the IDs of specfiles matching a query in the given [`QueryMode`]
+    ///
+    /// Matches exactly like [`SpecBase::query_specfiles_with_mode`] but only
+    /// fetches the IDs.
+    ///
+    /// # Arguments
+    /// * `query` - The search terms to look for
+    /// * `mode` - Whether to match the query as a phrase, or require all or any of its words
+    ///
+    /// # Returns
+    /// * `Ok(Vec<i64>)` - IDs of matching specfiles in ascending order
+    /// * `Err(Error)` - Failed to query database
+    pub fn query_ids_with_mode(&self, query: &str, mode: QueryMode) -> Result<Vec<i64>> {
+        self.with_query_timeout(|| {
+            let (condition, patterns) = search_condition(query, mode);
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT id FROM specfiles WHERE deleted_at IS NULL AND ({}) ORDER BY id",
+                condition
+            ))?;
+
+            let ids = stmt
+                .query_map(params_from_iter(patterns), |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(ids)
+        })
+    }
+
+    /// Counts the specfiles matching a fulltext query without loading them
+    ///
+    /// Matches the same specfiles as [`SpecBase::query_specfiles`].
+    ///
+    /// # Arguments
+    /// * `query` - The search term to look for
+    ///
+    /// # Returns
+    /// * `Ok(u64)` - Number of matching specfiles
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// println!("{} matches", spec_db.count_query("auth").unwrap());
+    /// ```
+    pub fn count_query(&self, query: &str) -> Result<u64> {
+        self.with_query_timeout(|| {
+            let (condition, patterns) = search_condition(query, QueryMode::Phrase);
+            let count = self.conn.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM specfiles WHERE deleted_at IS NULL AND ({})",
+                    condition
+                ),
+                params_from_iter(patterns),
+                |row| row.get(0),
+            )?;
+            Ok(count)
+        })
+    }
+
+    /// Lists specfiles whose name matches a glob pattern
+    ///
+    /// `*` matches any sequence of characters and `?` matches a single
+    /// character; every other character matches itself. The pattern is
+    /// translated to SQL `LIKE`, so matching is case-insensitive for ASCII
+    /// letters. Only names are searched, not descriptions or content.
+    ///
+    /// # Arguments
+    /// * `pattern` - Glob pattern to match names against, e.g. `"API-*"`
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - Matching specfiles, ordered by ID
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for spec in spec_db.read_by_name_glob("auth-*").unwrap() {
+    ///     println!("{} (ID: {})", spec.name, spec.id.unwrap());
+    /// }
+    /// ```
+    pub fn read_by_name_glob(&self, pattern: &str) -> Result<Vec<Specfile>> {
+        self.with_query_timeout(|| {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles
+                 WHERE deleted_at IS NULL AND name LIKE ?1 ESCAPE '\\' ORDER BY id"
+            ))?;
+
+            let specfiles = stmt
+                .query_map(params![glob_to_like(pattern)], specfile_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(specfiles)
+        })
+    }
+
+    /// Lists specfiles written in a format older than `current`
+    ///
+    /// # Arguments
+    /// * `current` - Format version that specfiles are expected to follow,
+    ///   usually [`CURRENT_FORMAT_VERSION`]
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - Specfiles with a lower format version, ordered by ID
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{SpecBase, CURRENT_FORMAT_VERSION};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for spec in spec_db.list_outdated_format(CURRENT_FORMAT_VERSION).unwrap() {
+    ///     println!("{} uses format {}", spec.name, spec.format_version);
+    /// }
+    /// ```
+    pub fn list_outdated_format(&self, current: i32) -> Result<Vec<Specfile>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SPECFILE_COLUMNS} FROM specfiles
+             WHERE deleted_at IS NULL AND format_version < ?1 ORDER BY id"
+        ))?;
+
+        let specfiles = stmt
+            .query_map(params![current], specfile_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(specfiles)
+    }
+
+    /// Returns up to `n` specfiles chosen at random
+    ///
+    /// Every call draws a new sample using `ORDER BY RANDOM()`, which sorts
+    /// the whole table and is meant for occasional reviews rather than hot
+    /// paths.
+    ///
+    /// # Arguments
+    /// * `n` - Maximum number of specfiles to return
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - Randomly chosen specfiles, fewer if the database holds less than `n`
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for spec in spec_db.sample(5).unwrap() {
+    ///     println!("Review: {} (ID: {})", spec.name, spec.id.unwrap());
+    /// }
+    /// ```
+    pub fn sample(&self, n: u32) -> Result<Vec<Specfile>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SPECFILE_COLUMNS} FROM specfiles
+             WHERE deleted_at IS NULL ORDER BY RANDOM() LIMIT ?1"
+        ))?;
+
+        let specfiles = stmt
+            .query_map(params![n], specfile_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(specfiles)
+    }
+
+    /// Counts word frequencies across the content of all specfiles
+    ///
+    /// Content is split into words at every character that is not
+    /// alphanumeric, and words are lowercased before counting. Rows are
+    /// streamed from the database one at a time, so memory use grows with
+    /// the number of distinct words rather than the size of the corpus.
+    ///
+    /// # Arguments
+    /// * `top_n` - Maximum number of words to return
+    /// * `exclude_stop_words` - Skip common English words such as "the" or "and"
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(String, u64)>)` - Words with their counts, most frequent first.
+    ///   Words with equal counts are ordered alphabetically.
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (word, count) in spec_db.vocabulary(10, true).unwrap() {
+    ///     println!("{}: {}", word, count);
+    /// }
+    /// ```
+    pub fn vocabulary(&self, top_n: usize, exclude_stop_words: bool) -> Result<Vec<(String, u64)>> {
+        self.with_query_timeout(|| {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT content FROM specfiles WHERE deleted_at IS NULL")?;
+            let mut rows = stmt.query([])?;
+
+            let mut counts = HashMap::new();
+            while let Some(row) = rows.next()? {
+                let content: String = row.get(0)?;
+                count_words(&content, exclude_stop_words, &mut counts);
+            }
+
+            Ok(top_words(counts, top_n))
+        })
+    }
+
+    /// Counts word frequencies in the content of a single specfile
+    ///
+    /// Words are split and lowercased the same way as in [`SpecBase::vocabulary`].
+    ///
+    /// # Arguments
+    /// * `id` - ID of the specfile to analyze
+    /// * `top_n` - Maximum number of words to return
+    /// * `exclude_stop_words` - Skip common English words such as "the" or "and"
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(String, u64)>)` - Words with their counts, most frequent first.
+    ///   Words with equal counts are ordered alphabetically.
+    /// * `Err(Error)` - Specfile not found or database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (word, count) in spec_db.word_frequencies(1, 10, true).unwrap() {
+    ///     println!("{}: {}", word, count);
+    /// }
+    /// ```
+    pub fn word_frequencies(
+        &self,
+        id: i64,
+        top_n: usize,
+        exclude_stop_words: bool,
+    ) -> Result<Vec<(String, u64)>> {
+        let content: String = self
+            .conn
+            .query_row(
+                "SELECT content FROM specfiles WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SpecError::SpecfileNotFound(id))?;
+
+        let mut counts = HashMap::new();
+        count_words(&content, exclude_stop_words, &mut counts);
+        Ok(top_words(counts, top_n))
+    }
+
+    /// Finds the lines of a specfile's content that match a pattern
+    ///
+    /// # Arguments
+    /// * `id` - ID of the specfile to search
+    /// * `pattern` - Text to look for, or a regular expression if `regex` is set
+    /// * `regex` - Interpret `pattern` as a regular expression instead of literal text
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(usize, String)>)` - Matching lines with their 1-based line numbers
+    /// * `Err(Error)` - Specfile not found, invalid regular expression, or database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (number, line) in spec_db.grep_in_specfile(1, "TODO", false).unwrap() {
+    ///     println!("{}: {}", number, line);
+    /// }
+    /// ```
+    pub fn grep_in_specfile(
+        &self,
+        id: i64,
+        pattern: &str,
+        regex: bool,
+    ) -> Result<Vec<(usize, String)>> {
+        let matcher = if regex {
+            Some(Regex::new(pattern).context("Invalid search pattern")?)
+        } else {
+            None
+        };
+        let content: String = self
+            .conn
+            .query_row(
+                "SELECT content FROM specfiles WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SpecError::SpecfileNotFound(id))?;
+
+        let matches = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| match &matcher {
+                Some(matcher) => matcher.is_match(line),
+                None => line.contains(pattern),
+            })
+            .map(|(index, line)| (index + 1, line.to_string()))
+            .collect();
+        Ok(matches)
+    }
+
+    /// Counts specfiles by the first character of their name
+    ///
+    /// Letters are case-folded to uppercase, so "auth" and "Audit" are both
+    /// counted under 'A'. Names starting with anything other than a letter,
+    /// including empty names, are grouped under '#'.
+    ///
+    /// # Returns
+    /// * `Ok(BTreeMap<char, u64>)` - Counts keyed by initial, in sorted order.
+    ///   Initials without any specfiles are omitted.
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (initial, count) in spec_db.index_by_initial().unwrap() {
+    ///     println!("{} ({})", initial, count);
+    /// }
+    /// ```
+    pub fn index_by_initial(&self) -> Result<BTreeMap<char, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM specfiles WHERE deleted_at IS NULL")?;
+        let mut rows = stmt.query([])?;
+
+        let mut index = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(0)?;
+            *index.entry(initial(&name)).or_insert(0) += 1;
+        }
+
+        Ok(index)
+    }
+}
+
+impl SpecBase {
+    /// Compares this database with another SpecBase database file
+    ///
+    /// Specfiles are matched by name, since IDs differ between databases.
+    /// Contents are compared by hash, so neither database is held in memory
+    /// in full. If a name occurs more than once in a database, the contents
+    /// of all specfiles with that name are compared as a set. The other
+    /// database is opened read-only and is never created or modified.
+    ///
+    /// # Arguments
+    /// * `other_path` - Path to the other SpecBase database file
+    ///
+    /// # Returns
+    /// * `Ok(DbDiff)` - Names only in this database, only in the other, and changed in both
+    /// * `Err(Error)` - The other database could not be opened or queried
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let diff = spec_db.diff_database("shared/specbase.db").unwrap();
+    /// for name in &diff.changed {
+    ///     println!("changed: {}", name);
+    /// }
+    /// ```
+    pub fn diff_database(&self, other_path: impl AsRef<Path>) -> Result<DbDiff> {
+        let other = Connection::open_with_flags(
+            other_path.as_ref(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        let ours = content_hashes_by_name(&self.conn)?;
+        let mut theirs = content_hashes_by_name(&other)?;
+
+        let mut diff = DbDiff::default();
+        for (name, hashes) in ours {
+            match theirs.remove(&name) {
+                None => diff.only_in_self.push(name),
+                Some(other_hashes) if other_hashes != hashes => diff.changed.push(name),
+                Some(_) => {}
+            }
+        }
+        diff.only_in_other = theirs.into_keys().collect();
+
+        Ok(diff)
+    }
+}
+
+impl SpecBase {
+    /// Reads the pragma settings currently active on the connection
+    ///
+    /// Useful for diagnosing slow or locked databases.
+    ///
+    /// # Returns
+    /// * `Ok(PragmaInfo)` - The current pragma values
+    /// * `Err(Error)` - Failed to query a pragma
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let pragmas = spec_db.pragmas().unwrap();
+    /// println!("journal_mode: {}", pragmas.journal_mode);
+    /// ```
+    pub fn pragmas(&self) -> Result<PragmaInfo> {
+        Ok(PragmaInfo {
+            journal_mode: self.pragma_value("journal_mode")?,
+            synchronous: self.pragma_value("synchronous")?,
+            busy_timeout: self.pragma_value("busy_timeout")?,
+            cache_size: self.pragma_value("cache_size")?,
+            page_size: self.pragma_value("page_size")?,
+        })
+    }
+
+    /// Reads a single pragma value from the main database
+    fn pragma_value<T: rusqlite::types::FromSql>(&self, name: &str) -> Result<T> {
+        Ok(self.conn.pragma_query_value(None, name, |row| row.get(0))?)
+    }
+
+    /// Copies pages from the write-ahead log back into the database file
+    ///
+    /// Wraps `PRAGMA wal_checkpoint(<mode>)`. In WAL mode the `-wal` file
+    /// grows until a checkpoint runs; this lets callers control that growth
+    /// explicitly. On a database that is not in WAL mode this is a no-op and
+    /// the returned page counts are -1.
+    ///
+    /// # Arguments
+    /// * `mode` - How aggressively to checkpoint
+    ///
+    /// # Returns
+    /// * `Ok(CheckpointResult)` - Pages in the WAL and pages written back
+    /// * `Err(Error)` - Failed to run the checkpoint
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::{CheckpointMode, SpecBase};
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let result = spec_db.checkpoint(CheckpointMode::Truncate).unwrap();
+    /// println!("{} pages written", result.checkpointed_pages);
+    /// ```
+    pub fn checkpoint(&self, mode: CheckpointMode) -> Result<CheckpointResult> {
+        let sql = format!("PRAGMA wal_checkpoint({})", mode.as_sql());
+        let result = self.conn.query_row(&sql, [], |row| {
+            Ok(CheckpointResult {
+                busy: row.get::<_, i64>(0)? != 0,
+                log_pages: row.get(1)?,
+                checkpointed_pages: row.get(2)?,
+            })
+        })?;
+        Ok(result)
+    }
+
+    /// Returns the creation and update events of all specfiles in chronological order
+    ///
+    /// The database keeps no revision history, so each specfile contributes
+    /// its `created_at` timestamp and, if it differs, its `updated_at`
+    /// timestamp; earlier updates are not recorded. Events with the same
+    /// timestamp are ordered by specfile ID, and specfiles without
+    /// timestamps are left out.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<TimelineEntry>)` - Events, oldest first
+    /// * `Err(Error)` - Failed to read from the database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for entry in spec_db.timeline().unwrap() {
+    ///     println!("{} {} {}", entry.timestamp, entry.event, entry.name);
+    /// }
+    /// ```
+    pub fn timeline(&self) -> Result<Vec<TimelineEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT created_at, id, name, 0 FROM specfiles
+             WHERE created_at IS NOT NULL AND deleted_at IS NULL
+             UNION ALL
+             SELECT updated_at, id, name, 1 FROM specfiles
+             WHERE updated_at IS NOT NULL AND updated_at IS NOT created_at AND deleted_at IS NULL
+             ORDER BY 1, 2, 4",
+        )?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(TimelineEntry {
+                    timestamp: row.get(0)?,
+                    specfile_id: row.get(1)?,
+                    name: row.get(2)?,
+                    event: match row.get::<_, i64>(3)? {
+                        0 => TimelineEvent::Created,
+                        _ => TimelineEvent::Updated,
+                    },
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// Counts the specfiles with a tag by the day they were created
+    ///
+    /// Shows how an area of the documentation grows over time. Days are
+    /// taken from the UTC `created_at` timestamp; days without new specfiles
+    /// are left out, as are specfiles without timestamps.
+    ///
+    /// # Arguments
+    /// * `tag` - The tag to count; surrounding whitespace is ignored
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(String, u64)>)` - Days as `YYYY-MM-DD` text with the number of specfiles created that day, oldest first
+    /// * `Err(Error)` - Failed to read from the database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (day, created) in spec_db.tag_growth("auth").unwrap() {
+    ///     println!("{} +{}", day, created);
+    /// }
+    /// ```
+    pub fn tag_growth(&self, tag: &str) -> Result<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT substr(specfiles.created_at, 1, 10) AS day, COUNT(*) FROM specfiles
+             JOIN specfile_tags ON specfile_tags.specfile_id = specfiles.id
+             JOIN tags ON tags.id = specfile_tags.tag_id
+             WHERE tags.name = ?1
+                 AND specfiles.created_at IS NOT NULL AND specfiles.deleted_at IS NULL
+             GROUP BY day
+             ORDER BY day",
+        )?;
+        let growth = stmt
+            .query_map(params![tag.trim()], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(growth)
+    }
+
+    /// Returns the path of the database file, if it is backed by a file
+    pub fn path(&self) -> Option<&str> {
+        self.conn.path().filter(|path| !path.is_empty())
+    }
+
+    /// Returns the underlying SQLite connection for custom SQL
+    ///
+    /// This is an escape hatch for queries the library doesn't offer, such
+    /// as analytics. The connection is a `rusqlite` 0.32 [`Connection`]; add
+    /// `rusqlite` as a dependency to use its parameter macros and types.
+    /// The schema at [`SCHEMA_VERSION`] is:
+    ///
+    /// - `specfiles`: `id INTEGER PRIMARY KEY`, `name`, `description`, and
+    ///   `content` (`TEXT NOT NULL`), `slug` (`TEXT`, unique),
+    ///   `format_version` (`INTEGER`), `created_at` and `updated_at` (RFC 3339
+    ///   text in UTC), `metadata_json` (`TEXT`, valid JSON or `NULL`), and
+    ///   `deleted_at` (`TEXT`, set while the specfile is in the trash)
+    /// - `tags`: `id INTEGER PRIMARY KEY` and `name TEXT NOT NULL UNIQUE`
+    /// - `specfile_tags`: `specfile_id` and `tag_id`, linking specfiles to tags
+    /// - `specfiles_fts`: FTS5 index over `name`, `description`, and
+    ///   `content`, with `rowid` equal to `specfiles.id`
+    /// - `undo_journal`: snapshot used by [`SpecBase::undo_last`]
+    ///
+    /// Filter on `deleted_at IS NULL` to skip trashed specfiles, as every
+    /// method of this library does. Reading is always safe. Writing bypasses
+    /// the library's checks: triggers keep the full-text index in sync and
+    /// discard the undo snapshot, but timestamps, slugs, and naming rules are
+    /// up to the caller. Databases opened with [`SpecBase::init_immutable`]
+    /// reject writes at the SQLite level. The schema may change in later
+    /// versions; check [`SpecBase::schema_version`] if that matters.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let mut stmt = spec_db
+    ///     .connection()
+    ///     .prepare(
+    ///         "SELECT substr(created_at, 1, 7) AS month, COUNT(*) FROM specfiles
+    ///          WHERE deleted_at IS NULL GROUP BY month ORDER BY month",
+    ///     )
+    ///     .unwrap();
+    /// let rows = stmt
+    ///     .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+    ///     .unwrap();
+    /// for row in rows {
+    ///     let (month, count) = row.unwrap();
+    ///     println!("{month}: {count}");
+    /// }
+    /// ```
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Returns the schema version of the database
+    ///
+    /// After opening, this is always [`SCHEMA_VERSION`]: older databases are
+    /// migrated, and newer ones fail to open.
+    pub fn schema_version(&self) -> Result<i64> {
+        self.pragma_value("user_version")
+    }
+}
+
+impl SpecBase {
+    /// Writes the schema and all data as a plain SQL script
+    ///
+    /// The output has the same shape as the `sqlite3` CLI's `.dump`: a
+    /// transaction containing a `CREATE TABLE` statement and `INSERT`
+    /// statements for every table, followed by indexes and triggers. It can
+    /// be loaded into an empty database with `sqlite3 new.db < dump.sql`.
+    /// String literals are single-quoted with embedded quotes doubled, and
+    /// blobs are written as hex literals. Full-text indexes are not written
+    /// row by row but rebuilt from the restored data, and the schema version
+    /// is kept so the restored database isn't migrated again.
+    ///
+    /// # Arguments
+    /// * `writer` - Destination for the SQL script
+    ///
+    /// # Returns
+    /// * `Ok(())` - The complete dump was written
+    /// * `Err(Error)` - Failed to query the database or write the output
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    /// use std::fs::File;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let file = File::create("specs.sql").unwrap();
+    /// spec_db.dump_sql(file).unwrap();
+    /// ```
+    pub fn dump_sql<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = writer;
+        writeln!(writer, "PRAGMA foreign_keys=OFF;")?;
+        writeln!(writer, "BEGIN TRANSACTION;")?;
+        writeln!(writer, "PRAGMA user_version={};", self.schema_version()?)?;
+
+        // Shadow tables hold the data of virtual tables and are created with them
+        let mut stmt = self.conn.prepare(
+            "SELECT m.name, m.sql, t.type = 'virtual' FROM sqlite_master AS m
+             JOIN pragma_table_list AS t ON t.schema = 'main' AND t.name = m.name
+             WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' AND m.sql IS NOT NULL
+                 AND t.type != 'shadow'
+             ORDER BY m.rowid",
+        )?;
+        let tables = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (table, sql, is_virtual) in &tables {
+            writeln!(writer, "{};", sql)?;
+            if !is_virtual {
+                self.dump_rows(table, &mut writer)?;
+            }
+        }
+        for (table, sql, is_virtual) in &tables {
+            if *is_virtual && sql.to_ascii_lowercase().contains("using fts5") {
+                let table = quote_identifier(table);
+                writeln!(writer, "INSERT INTO {table}({table}) VALUES('rebuild');")?;
+            }
+        }
+
+        // AUTOINCREMENT counters, so restored databases don't reuse IDs either
+        let has_sequence: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'sqlite_sequence')",
             [],
+            |row| row.get(0),
+        )?;
+        if has_sequence {
+            writeln!(writer, "DELETE FROM sqlite_sequence;")?;
+            self.dump_rows("sqlite_sequence", &mut writer)?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT sql FROM sqlite_master
+             WHERE type IN ('index', 'trigger', 'view') AND sql IS NOT NULL
+             ORDER BY rowid",
         )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            writeln!(writer, "{};", row.get::<_, String>(0)?)?;
+        }
+
+        writeln!(writer, "COMMIT;")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes an `INSERT` statement for every row of `table`
+    fn dump_rows<W: Write>(&self, table: &str, writer: &mut W) -> Result<()> {
+        let table = quote_identifier(table);
+        let mut stmt = self.conn.prepare(&format!("SELECT * FROM {}", table))?;
+        let column_count = stmt.column_count();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            write!(writer, "INSERT INTO {} VALUES(", table)?;
+            for index in 0..column_count {
+                if index > 0 {
+                    write!(writer, ",")?;
+                }
+                write_sql_literal(row.get_ref(index)?, writer)?;
+            }
+            writeln!(writer, ");")?;
+        }
+        Ok(())
+    }
+}
+
+/// Maximum time to wait for a linked server when checking links
+#[cfg(feature = "net")]
+const LINK_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+impl SpecBase {
+    /// Finds external links in specfile content that are broken
+    ///
+    /// Collects every `http://` and `https://` URL from the markdown content
+    /// of all specfiles and reports those with a malformed host or port.
+    /// With the `net` feature enabled, well-formed URLs are also checked
+    /// with a HEAD request (falling back to GET) and reported if the server
+    /// can't be reached within 10 seconds or answers with a 4xx/5xx status.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(i64, Vec<String>)>)` - IDs of specfiles with broken links and
+    ///   their broken URLs, ordered by ID. Specfiles without problems are omitted.
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (id, urls) in spec_db.find_broken_links().unwrap() {
+    ///     println!("Specfile {} has {} broken links", id, urls.len());
+    /// }
+    /// ```
+    pub fn find_broken_links(&self) -> Result<Vec<(i64, Vec<String>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content FROM specfiles WHERE deleted_at IS NULL ORDER BY id")?;
+        let mut rows = stmt.query([])?;
+
+        // Collect URLs first so no read is held open during network checks
+        let mut specfile_urls = Vec::new();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let content: String = row.get(1)?;
+            let mut urls: Vec<String> = Vec::new();
+            for url in links::extract_urls(&content) {
+                if !urls.iter().any(|seen| seen == url) {
+                    urls.push(url.to_string());
+                }
+            }
+            if !urls.is_empty() {
+                specfile_urls.push((id, urls));
+            }
+        }
+
+        #[cfg(feature = "net")]
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(LINK_CHECK_TIMEOUT))
+            .http_status_as_error(false)
+            .build()
+            .into();
+        let mut checked: HashMap<String, bool> = HashMap::new();
+
+        let mut broken = Vec::new();
+        for (id, urls) in specfile_urls {
+            let broken_urls: Vec<String> = urls
+                .into_iter()
+                .filter(|url| {
+                    let ok = *checked.entry(url.clone()).or_insert_with(|| {
+                        let ok = links::is_well_formed(url);
+                        #[cfg(feature = "net")]
+                        let ok = ok && links::is_reachable(&agent, url);
+                        ok
+                    });
+                    !ok
+                })
+                .collect();
+            if !broken_urls.is_empty() {
+                broken.push((id, broken_urls));
+            }
+        }
+
+        Ok(broken)
+    }
 
-        Ok(Self { conn })
+    /// Finds `spec://<id>` references to specfiles that don't exist
+    ///
+    /// Specfiles refer to each other with links such as `spec://42` in their
+    /// content. This checks every reference in the database, e.g. before
+    /// publishing, to catch links to purged or never created specfiles.
+    /// References to specfiles in the trash can still be resolved by
+    /// restoring them, so they are reported by
+    /// [`SpecBase::find_trashed_references`] instead.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(i64, Vec<i64>)>)` - IDs of specfiles with dangling references
+    ///   and the referenced IDs that don't exist, ordered by ID. Specfiles
+    ///   without problems are omitted.
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (id, missing) in spec_db.validate_all_references().unwrap() {
+    ///     println!("Specfile {} refers to missing specfiles {:?}", id, missing);
+    /// }
+    /// ```
+    pub fn validate_all_references(&self) -> Result<Vec<(i64, Vec<i64>)>> {
+        let trashed = self.trashed_ids()?;
+        self.references_where(|existing, reference| {
+            !existing.contains(&reference) && !trashed.contains(&reference)
+        })
+    }
+
+    /// Finds `spec://<id>` references to specfiles in the trash
+    ///
+    /// These links are broken until the referenced specfile is restored,
+    /// e.g. the back-references of a specfile split with `delete_original`.
+    /// They are reported apart from references to specfiles that don't
+    /// exist at all, see [`SpecBase::validate_all_references`].
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(i64, Vec<i64>)>)` - IDs of specfiles with references into
+    ///   the trash and the trashed IDs they refer to, ordered by ID.
+    ///   Specfiles without such references are omitted.
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for (id, trashed) in spec_db.find_trashed_references().unwrap() {
+    ///     println!("Specfile {} refers to trashed specfiles {:?}", id, trashed);
+    /// }
+    /// ```
+    pub fn find_trashed_references(&self) -> Result<Vec<(i64, Vec<i64>)>> {
+        let trashed = self.trashed_ids()?;
+        self.references_where(|_, reference| trashed.contains(&reference))
+    }
+
+    /// Returns the IDs of all specfiles in the trash
+    fn trashed_ids(&self) -> Result<HashSet<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM specfiles WHERE deleted_at IS NOT NULL")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<HashSet<i64>>>()?;
+        Ok(ids)
+    }
+
+    /// Collects the `spec://<id>` references of live specfiles that `matches`
+    /// accepts, given the IDs of all live specfiles
+    fn references_where(
+        &self,
+        matches: impl Fn(&HashSet<i64>, i64) -> bool,
+    ) -> Result<Vec<(i64, Vec<i64>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content FROM specfiles WHERE deleted_at IS NULL ORDER BY id")?;
+        let specfiles = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let existing: HashSet<i64> = specfiles.iter().map(|(id, _)| *id).collect();
+
+        let mut found = Vec::new();
+        for (id, content) in &specfiles {
+            let mut references: Vec<i64> = Vec::new();
+            for reference in links::extract_spec_references(content) {
+                if matches(&existing, reference) && !references.contains(&reference) {
+                    references.push(reference);
+                }
+            }
+            if !references.is_empty() {
+                found.push((*id, references));
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Finds cycles of `spec://<id>` references between specfiles
+    ///
+    /// Cycles break anything that follows references as a tree, such as
+    /// rendering nested specs. A specfile that refers to itself is a cycle
+    /// of one. Each group of specfiles that refer to each other in a circle
+    /// is reported at least once, though not every possible cycle through
+    /// it is listed. References to missing specfiles are ignored; see
+    /// [`SpecBase::validate_all_references`] for those.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Vec<i64>>)` - Each cycle as the IDs along it, in reference
+    ///   order; the last one refers back to the first
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for cycle in spec_db.find_cycles().unwrap() {
+    ///     println!("Circular references: {:?}", cycle);
+    /// }
+    /// ```
+    pub fn find_cycles(&self) -> Result<Vec<Vec<i64>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content FROM specfiles WHERE deleted_at IS NULL ORDER BY id")?;
+        let mut rows = stmt.query([])?;
+
+        let mut graph = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let mut references = links::extract_spec_references(row.get_ref(1)?.as_str()?);
+            references.sort_unstable();
+            references.dedup();
+            graph.insert(row.get::<_, i64>(0)?, references);
+        }
+
+        Ok(links::find_cycles(&graph))
+    }
+}
+
+/// Character sequences that typically result from UTF-8 text being decoded
+/// as Windows-1252 or Latin-1 and re-encoded as UTF-8
+const MOJIBAKE_SEQUENCES: &[&str] = &[
+    "Ã©",
+    "Ã¨",
+    "Ãª",
+    "Ã«",
+    "Ã¡",
+    "Ã\u{a0}",
+    "Ã¢",
+    "Ã¤",
+    "Ã£",
+    "Ã¥",
+    "Ã§",
+    "Ã\u{ad}",
+    "Ã®",
+    "Ã¯",
+    "Ã±",
+    "Ã³",
+    "Ã²",
+    "Ã´",
+    "Ã¶",
+    "Ãµ",
+    "Ãº",
+    "Ã¹",
+    "Ã»",
+    "Ã¼",
+    "Ã‰",
+    "Ã„",
+    "Ã–",
+    "Ãœ",
+    "ÃŸ",
+    "â€™",
+    "â€˜",
+    "â€œ",
+    "â€\u{9d}",
+    "â€“",
+    "â€”",
+    "â€¦",
+    "â€¢",
+    "Â\u{a0}",
+    "Â©",
+    "Â®",
+    "Â°",
+    "Â±",
+];
+
+impl SpecBase {
+    /// Finds specfiles whose content looks like double-encoded UTF-8
+    ///
+    /// Flags content containing sequences such as `Ã©` (for `é`) or `â€™`
+    /// (for `’`), which appear when UTF-8 text is decoded with the wrong
+    /// character set and saved again. This is a heuristic for review: it may
+    /// flag text that legitimately contains these sequences and it does not
+    /// repair anything.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<i64>)` - IDs of suspicious specfiles in ascending order
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for id in spec_db.find_suspicious_encoding().unwrap() {
+    ///     println!("Specfile {} may contain mojibake", id);
+    /// }
+    /// ```
+    pub fn find_suspicious_encoding(&self) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content FROM specfiles WHERE deleted_at IS NULL ORDER BY id")?;
+        let mut rows = stmt.query([])?;
+
+        let mut suspicious = Vec::new();
+        while let Some(row) = rows.next()? {
+            let content: String = row.get(1)?;
+            if MOJIBAKE_SEQUENCES
+                .iter()
+                .any(|sequence| content.contains(sequence))
+            {
+                suspicious.push(row.get(0)?);
+            }
+        }
+
+        Ok(suspicious)
+    }
+
+    /// Finds stub specfiles whose content is empty or nearly so
+    ///
+    /// A specfile is a stub if its content, with leading and trailing
+    /// whitespace (spaces, tabs, and line breaks) removed, is shorter than
+    /// `min_length` characters. The length is computed by SQLite, so only the
+    /// stubs themselves are loaded.
+    ///
+    /// # Arguments
+    /// * `min_length` - Minimum number of characters a specfile must have
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Specfile>)` - Stub specfiles in ascending ID order
+    /// * `Err(Error)` - Failed to query database
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// for specfile in spec_db.find_stubs(50).unwrap() {
+    ///     println!("{} needs content", specfile.name);
+    /// }
+    /// ```
+    pub fn find_stubs(&self, min_length: usize) -> Result<Vec<Specfile>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SPECFILE_COLUMNS} FROM specfiles
+             WHERE deleted_at IS NULL AND length(trim(content, char(32, 9, 10, 13))) < ?1
+             ORDER BY id"
+        ))?;
+
+        let min_length = i64::try_from(min_length).unwrap_or(i64::MAX);
+        let specfiles = stmt
+            .query_map(params![min_length], specfile_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(specfiles)
+    }
+}
+
+#[cfg(feature = "rst")]
+impl SpecBase {
+    /// Retrieves the content of a specfile converted to reStructuredText
+    ///
+    /// Uses a basic built-in converter that handles headings, fenced code
+    /// blocks, bullet lists, inline code, and inline links. Other markdown
+    /// is passed through unchanged.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to convert
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The content as reStructuredText
+    /// * `Err(Error)` - Specfile not found or database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// std::fs::write("spec.rst", spec_db.read_specfile_rst(1).unwrap()).unwrap();
+    /// ```
+    pub fn read_specfile_rst(&self, id: i64) -> Result<String> {
+        let specfile = self.read_specfile(id)?;
+        Ok(rst::markdown_to_rst(&specfile.content))
+    }
+}
+
+#[cfg(feature = "confluence")]
+impl SpecBase {
+    /// Retrieves the content of a specfile converted to Confluence storage format
+    ///
+    /// The result is the XHTML Confluence stores pages in, ready to paste
+    /// into the source editor or to send as the `storage` body through the
+    /// REST API. A basic built-in converter handles headings, paragraphs,
+    /// bullet and numbered lists, pipe tables, fenced code blocks (as `code`
+    /// macros), inline code, strong and emphasized text, and links. Nested
+    /// lists are flattened, and other markdown is kept as paragraph text.
+    ///
+    /// Requires the `confluence` feature.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the specfile to convert
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The content in Confluence storage format
+    /// * `Err(Error)` - Specfile not found or database error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use lib_specbase::SpecBase;
+    ///
+    /// let spec_db = SpecBase::init().unwrap();
+    /// let page = spec_db.read_specfile_confluence(1).unwrap();
+    /// std::fs::write("spec.xhtml", page).unwrap();
+    /// ```
+    pub fn read_specfile_confluence(&self, id: i64) -> Result<String> {
+        let specfile = self.read_specfile(id)?;
+        Ok(confluence::markdown_to_confluence(&specfile.content))
     }
 }
 
 impl SpecBase {
-    /// Creates a new specfile in the database
+    /// Writes all specfiles, including their tags, as a JSON array
+    ///
+    /// The output is meant for backups and can be read back with
+    /// [`SpecBase::import_json`].
     ///
     /// # Arguments
-    /// * `specfile` - The specfile to create. The `id` field will be ignored.
+    /// * `writer` - Destination of the JSON text
     ///
     /// # Returns
-    /// * `Ok(i64)` - ID of the newly created specfile
-    /// * `Err(Error)` - Failed to create specfile in database
+    /// * `Ok(usize)` - Number of exported specfiles
+    /// * `Err(Error)` - Failed to query database or write the output
     ///
     /// # Example
     /// ```no_run
-    /// use lib_specbase::{SpecBase, Specfile};
+    /// use lib_specbase::SpecBase;
+    /// use std::fs::File;
     ///
     /// let spec_db = SpecBase::init().unwrap();
-    /// let spec = Specfile {
-    ///     id: None,
-    ///     name: "Example".to_string(),
-    ///     description: "An example spec".to_string(),
-    ///     content: "# Example\nThis is an example.".to_string(),
-    /// };
-    ///
-    /// let id = spec_db.create_specfile(&spec).expect("Failed to create specfile");
+    /// let file = File::create("specs.json").unwrap();
+    /// spec_db.export_json(file).unwrap();
     /// ```
-    pub fn create_specfile(&self, specfile: &Specfile) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO specfiles (name, description, content) VALUES (?1, ?2, ?3)",
-            params![specfile.name, specfile.description, specfile.content],
-        )?;
-        Ok(self.conn.last_insert_rowid())
+    pub fn export_json<W: Write>(&self, mut writer: W) -> Result<usize> {
+        let specfiles = self.list_specfiles()?;
+        serde_json::to_writer_pretty(&mut writer, &specfiles)?;
+        writer.write_all(b"\n")?;
+        Ok(specfiles.len())
     }
 
-    /// Retrieves a specfile from the database by its ID
+    /// Exports all specfiles into a directory of markdown files
+    ///
+    /// Each specfile is written as `<id>-<slug>.md` in the same format as
+    /// [`SpecBase::export_zip`]: a frontmatter block holding its ID, name,
+    /// description, format version, and tags, followed by its content. The
+    /// directory is created if needed, and existing files with the same
+    /// names are overwritten; other files are left alone.
     ///
     /// # Arguments
-    /// * `id` - The ID of the specfile to retrieve
+    /// * `dir` - Directory to write the markdown files to
     ///
     /// # Returns
-    /// * `Ok(Specfile)` - The requested specfile
-    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
-    /// * `Err(Error)` - Other database error occurred
+    /// * `Ok(usize)` - Number of files written
+    /// * `Err(Error)` - Failed to query the database or write a file
     ///
     /// # Example
     /// ```no_run
     /// use lib_specbase::SpecBase;
+    /// use std::path::Path;
     ///
     /// let spec_db = SpecBase::init().unwrap();
-    /// match spec_db.read_specfile(1) {
-    ///     Ok(spec) => println!("Found spec: {}", spec.name),
-    ///     Err(e) => eprintln!("Error: {}", e),
-    /// }
+    /// let count = spec_db.export_to_dir(Path::new("specs")).unwrap();
+    /// println!("Exported {} specfiles", count);
     /// ```
-    pub fn read_specfile(&self, id: i64) -> Result<Specfile> {
-        let specfile = self
-            .conn
-            .query_row(
-                "SELECT id, name, description, content FROM specfiles WHERE id = ?1",
-                params![id],
-                |row| {
-                    Ok(Specfile {
-                        id: Some(row.get(0)?),
-                        name: row.get(1)?,
-                        description: row.get(2)?,
-                        content: row.get(3)?,
-                    })
-                },
-            )
-            .map_err(|_| SpecError::SpecfileNotFound(id))?;
-        Ok(specfile)
+    pub fn export_to_dir(&self, dir: &Path) -> Result<usize> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        let specfiles = self.list_specfiles()?;
+        for specfile in &specfiles {
+            let path = dir.join(markdown::file_name(specfile));
+            std::fs::write(&path, markdown::to_markdown(specfile))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(specfiles.len())
     }
 
-    /// Updates an existing specfile in the database
+    /// Imports specfiles from a directory of markdown files
+    ///
+    /// Reads the output of [`SpecBase::export_to_dir`], or any hand-written
+    /// markdown files. Every file ending in `.md` directly inside `dir` is
+    /// parsed with an optional frontmatter block holding `name`,
+    /// `description`, and `tags`. Without a `name`, the first `# ` heading
+    /// or the file name is used. Subdirectories and other files are skipped.
+    /// The specfiles get new IDs, and the import runs in a single
+    /// transaction, so either every file is imported or none are.
     ///
     /// # Arguments
-    /// * `id` - The ID of the specfile to update
-    /// * `specfile` - The new specfile data. The `id` field will be ignored.
+    /// * `dir` - Directory containing the markdown files
     ///
     /// # Returns
-    /// * `Ok(())` - Successfully updated the specfile
-    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
-    /// * `Err(Error)` - Other database error occurred
+    /// * `Ok(Vec<i64>)` - IDs of the imported specfiles, in file name order
+    /// * `Err(Error)` - A file could not be read or is not valid UTF-8, or
+    ///   the database rejected an insert
     ///
     /// # Example
     /// ```no_run
-    /// use lib_specbase::{SpecBase, Specfile};
+    /// use lib_specbase::SpecBase;
+    /// use std::path::Path;
     ///
     /// let spec_db = SpecBase::init().unwrap();
-    /// let updated_spec = Specfile {
-    ///     id: Some(1),
-    ///     name: "Updated Example".to_string(),
-    ///     description: "Updated description".to_string(),
-    ///     content: "# Updated\nThis spec has been updated.".to_string(),
-    /// };
-    ///
-    /// match spec_db.update_specfile(1, &updated_spec) {
-    ///     Ok(_) => println!("Successfully updated specfile"),
-    ///     Err(e) => eprintln!("Error: {}", e),
-    /// }
+    /// let ids = spec_db.import_from_dir(Path::new("specs")).unwrap();
+    /// println!("Imported {} specfiles", ids.len());
     /// ```
-    pub fn update_specfile(&self, id: i64, specfile: &Specfile) -> Result<()> {
-        let rows_affected = self.conn.execute(
-            "UPDATE specfiles SET name = ?1, description = ?2, content = ?3 WHERE id = ?4",
-            params![specfile.name, specfile.description, specfile.content, id],
-        )?;
+    pub fn import_from_dir(&self, dir: &Path) -> Result<Vec<i64>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let is_markdown = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("md"));
+            if is_markdown && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
 
-        if rows_affected == 0 {
-            return Err(SpecError::SpecfileNotFound(id).into());
+        let mut specfiles = Vec::with_capacity(paths.len());
+        for path in &paths {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let fallback_name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            specfiles.push(markdown::from_markdown(&text, &fallback_name));
         }
-        Ok(())
+        self.create_specfiles(&specfiles)
     }
 
-    /// Deletes a specfile from the database
+    /// Creates specfiles, including their tags, from a JSON array
+    ///
+    /// Reads the output of [`SpecBase::export_json`]. The specfiles get new
+    /// IDs and timestamps; missing tags and format versions default to none
+    /// and [`CURRENT_FORMAT_VERSION`]. Tags are created as needed. The import
+    /// runs in a single transaction, so either every specfile is imported or
+    /// none are.
     ///
     /// # Arguments
-    /// * `id` - The ID of the specfile to delete
+    /// * `reader` - Source of the JSON text
     ///
     /// # Returns
-    /// * `Ok(())` - Successfully deleted the specfile
-    /// * `Err(SpecError::SpecfileNotFound)` - No specfile found with the given ID
-    /// * `Err(Error)` - Other database error occurred
+    /// * `Ok(Vec<i64>)` - IDs of the imported specfiles, in input order
+    /// * `Err(Error)` - The input is not a JSON array of specfiles, or the
+    ///   database rejected an insert
     ///
     /// # Example
     /// ```no_run
     /// use lib_specbase::SpecBase;
+    /// use std::fs::File;
     ///
     /// let spec_db = SpecBase::init().unwrap();
-    /// match spec_db.delete_specfile(1) {
-    ///     Ok(_) => println!("Successfully deleted specfile"),
-    ///     Err(e) => eprintln!("Error: {}", e),
-    /// }
+    /// let ids = spec_db.import_json(File::open("specs.json").unwrap()).unwrap();
+    /// println!("Imported {} specfiles", ids.len());
     /// ```
-    pub fn delete_specfile(&self, id: i64) -> Result<()> {
-        let rows_affected = self
-            .conn
-            .execute("DELETE FROM specfiles WHERE id = ?1", params![id])?;
-
-        if rows_affected == 0 {
-            return Err(SpecError::SpecfileNotFound(id).into());
-        }
-        Ok(())
+    pub fn import_json<R: Read>(&self, reader: R) -> Result<Vec<i64>> {
+        let specfiles: Vec<Specfile> =
+            serde_json::from_reader(reader).context("Invalid specfile JSON")?;
+        self.create_specfiles(&specfiles)
     }
 }
 
+#[cfg(feature = "zip")]
 impl SpecBase {
-    /// Lists all specfiles in the database
+    /// Imports specfiles from a zip archive of markdown files
+    ///
+    /// Reads the output of [`SpecBase::export_zip`], or any archive of
+    /// hand-written markdown files. Every entry ending in `.md` is parsed
+    /// like in [`SpecBase::import_from_dir`], with an optional frontmatter
+    /// block holding `name`, `description`, and `tags`. Without a `name`,
+    /// the first `# ` heading or the file name is used. All other entries
+    /// are skipped. The specfiles get new IDs, and the import runs in a
+    /// single transaction, so either every markdown file is imported or none
+    /// are.
+    ///
+    /// Requires the `zip` feature.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the zip archive
     ///
     /// # Returns
-    /// * `Ok(Vec<Specfile>)` - List of all specfiles
-    /// * `Err(Error)` - Failed to query database
+    /// * `Ok(Vec<i64>)` - IDs of the imported specfiles, in archive order
+    /// * `Err(Error)` - The archive could not be read, an entry is not valid
+    ///   UTF-8, or the database rejected an insert
     ///
     /// # Example
     /// ```no_run
     /// use lib_specbase::SpecBase;
     ///
     /// let spec_db = SpecBase::init().unwrap();
-    /// match spec_db.list_specfiles() {
-    ///     Ok(specs) => {
-    ///         for spec in specs {
-    ///             println!("Found spec: {} (ID: {})", spec.name, spec.id.unwrap());
-    ///         }
-    ///     },
-    ///     Err(e) => eprintln!("Error: {}", e),
-    /// }
+    /// let ids = spec_db.import_zip("specs.zip").unwrap();
+    /// println!("Imported {} specfiles", ids.len());
     /// ```
-    pub fn list_specfiles(&self) -> Result<Vec<Specfile>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, name, description, content FROM specfiles")?;
+    pub fn import_zip(&self, path: impl AsRef<Path>) -> Result<Vec<i64>> {
+        let file = std::fs::File::open(path.as_ref())?;
+        let mut archive = zip::ZipArchive::new(file)?;
 
-        let specfiles = stmt
-            .query_map([], |row| {
-                Ok(Specfile {
-                    id: Some(row.get(0)?),
-                    name: row.get(1)?,
-                    description: row.get(2)?,
-                    content: row.get(3)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut specfiles = Vec::new();
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            let entry_path = match entry.enclosed_name() {
+                Some(entry_path) if entry.is_file() => entry_path,
+                _ => continue,
+            };
+            let is_markdown = entry_path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("md"));
+            if !is_markdown {
+                continue;
+            }
 
-        Ok(specfiles)
+            let mut text = String::new();
+            entry
+                .read_to_string(&mut text)
+                .with_context(|| format!("Failed to read {}", entry_path.display()))?;
+            let fallback_name = entry_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            specfiles.push(markdown::from_markdown(&text, &fallback_name));
+        }
+        self.create_specfiles(&specfiles)
     }
 
-    /// Searches for specfiles using a fulltext query
+    /// Exports all specfiles into a zip archive of markdown files
     ///
-    /// Searches through the name, description, and content of all specfiles
-    /// for matches with the given query string. The search is case-insensitive
-    /// and uses SQL LIKE with wildcards.
+    /// Each specfile is written as `<id>-<slug>.md` in the same format as
+    /// [`SpecBase::export_to_dir`]: a frontmatter block holding its ID, name,
+    /// description, format version, and tags, followed by its content. The
+    /// archive can be read back with [`SpecBase::import_zip`]. An existing
+    /// file at `path` is overwritten.
+    ///
+    /// Requires the `zip` feature.
     ///
     /// # Arguments
-    /// * `query` - The search term to look for
+    /// * `path` - Path of the zip archive to create
     ///
     /// # Returns
-    /// * `Ok(Vec<Specfile>)` - List of matching specfiles
-    /// * `Err(Error)` - Failed to query database
+    /// * `Ok(usize)` - Number of specfiles written
+    /// * `Err(Error)` - Failed to query the database or write the archive
     ///
     /// # Example
     /// ```no_run
     /// use lib_specbase::SpecBase;
     ///
     /// let spec_db = SpecBase::init().unwrap();
-    /// match spec_db.query_specfiles("example") {
-    ///     Ok(specs) => {
-    ///         println!("Found {} matching specs:", specs.len());
-    ///         for spec in specs {
-    ///             println!("- {} (ID: {})", spec.name, spec.id.unwrap());
-    ///         }
-    ///     },
-    ///     Err(e) => eprintln!("Error: {}", e),
-    /// }
+    /// let count = spec_db.export_zip("specs.zip").unwrap();
+    /// println!("Exported {} specfiles", count);
     /// ```
-    pub fn query_specfiles(&self, query: &str) -> Result<Vec<Specfile>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, description, content FROM specfiles 
-             WHERE name LIKE ?1 OR description LIKE ?1 OR content LIKE ?1",
+    pub fn export_zip(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let file = std::fs::File::create(path.as_ref())?;
+        let mut archive = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        let specfiles = self.list_specfiles()?;
+        for specfile in &specfiles {
+            archive.start_file(markdown::file_name(specfile), options)?;
+            archive.write_all(markdown::to_markdown(specfile).as_bytes())?;
+        }
+        archive.finish()?;
+
+        Ok(specfiles.len())
+    }
+}
+
+/// Inserts a specfile on the given connection and returns its new ID
+///
+/// Takes a `Connection` rather than `&SpecBase` so it can run inside a
+/// transaction.
+fn insert_specfile(conn: &Connection, specfile: &Specfile) -> Result<i64> {
+    let slug = allocate_slug(conn, &specfile.name, None)?;
+    conn.execute(
+        &format!(
+            "INSERT INTO specfiles
+                (name, description, content, slug, format_version, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, {NOW_RFC3339}, {NOW_RFC3339})"
+        ),
+        params![
+            specfile.name,
+            specfile.description,
+            specfile.content,
+            slug,
+            specfile.format_version
+        ],
+    )
+    .map_err(|error| name_conflict(error, &specfile.name))?;
+    let id = conn.last_insert_rowid();
+    set_tags(conn, id, &specfile.tags)?;
+    Ok(id)
+}
+
+/// Replaces the tags of a specfile and removes tags no specfile uses anymore
+fn set_tags(conn: &Connection, id: i64, tags: &[String]) -> Result<()> {
+    conn.execute(
+        "DELETE FROM specfile_tags WHERE specfile_id = ?1",
+        params![id],
+    )?;
+    for tag in tags
+        .iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+    {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            params![tag],
         )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO specfile_tags (specfile_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![id, tag],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM specfile_tags)",
+        [],
+    )?;
+    Ok(())
+}
 
-        let search_pattern = format!("%{}%", query);
-        let specfiles = stmt
-            .query_map(params![search_pattern], |row| {
-                Ok(Specfile {
-                    id: Some(row.get(0)?),
+/// Creates the unique index on the names of specfiles outside the trash, unless it exists
+///
+/// Fails with [`SpecError::DuplicateNamesExist`] if names are already used more than once.
+fn enforce_unique_names(conn: &Connection) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1)",
+        params![UNIQUE_NAME_INDEX],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(());
+    }
+
+    let duplicates = conn
+        .prepare(
+            "SELECT name FROM specfiles WHERE deleted_at IS NULL
+             GROUP BY name HAVING COUNT(*) > 1 ORDER BY name",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    if !duplicates.is_empty() {
+        return Err(SpecError::DuplicateNamesExist(duplicates).into());
+    }
+
+    conn.execute(
+        &format!(
+            "CREATE UNIQUE INDEX {UNIQUE_NAME_INDEX} ON specfiles (name) WHERE deleted_at IS NULL"
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+/// Turns a violation of the unique name index into [`SpecError::DuplicateName`]
+fn name_conflict(error: rusqlite::Error, name: &str) -> anyhow::Error {
+    match &error {
+        rusqlite::Error::SqliteFailure(failure, Some(message))
+            if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                && message.ends_with("specfiles.name") =>
+        {
+            SpecError::DuplicateName(name.to_string()).into()
+        }
+        _ => error.into(),
+    }
+}
+
+/// Reads a specfile that isn't in the trash, e.g. within a transaction
+fn fetch_specfile(conn: &Connection, id: i64) -> Result<Specfile> {
+    let specfile = conn
+        .query_row(
+            &format!(
+                "SELECT {SPECFILE_COLUMNS} FROM specfiles WHERE id = ?1 AND deleted_at IS NULL"
+            ),
+            params![id],
+            specfile_from_row,
+        )
+        .optional()?
+        .ok_or(SpecError::SpecfileNotFound(id))?;
+    Ok(specfile)
+}
+
+/// Overwrites the specfile with the given ID and returns the number of rows changed
+fn write_specfile(conn: &Connection, id: i64, specfile: &Specfile) -> Result<usize> {
+    let slug = allocate_slug(conn, &specfile.name, Some(id))?;
+    let rows_affected = conn
+        .execute(
+            &format!(
+                "UPDATE specfiles
+             SET name = ?1, description = ?2, content = ?3, slug = ?4, format_version = ?5,
+                 updated_at = {NOW_RFC3339}
+             WHERE id = ?6 AND deleted_at IS NULL"
+            ),
+            params![
+                specfile.name,
+                specfile.description,
+                specfile.content,
+                slug,
+                specfile.format_version,
+                id
+            ],
+        )
+        .map_err(|error| name_conflict(error, &specfile.name))?;
+    if rows_affected > 0 {
+        set_tags(conn, id, &specfile.tags)?;
+    }
+    Ok(rows_affected)
+}
+
+/// Returns a slug for `name` that no other specfile uses
+///
+/// For an existing specfile (`id` is set), its current slug is kept as long
+/// as it was derived from the same name, so slugs only change when the name
+/// does. Collisions are resolved by appending `-2`, `-3`, and so on.
+fn allocate_slug(conn: &Connection, name: &str, id: Option<i64>) -> Result<String> {
+    let base = slug::base_slug(name);
+
+    if let Some(id) = id {
+        let current: Option<String> = conn
+            .query_row(
+                "SELECT slug FROM specfiles WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        if let Some(current) = current.filter(|current| slug::has_base(current, &base)) {
+            return Ok(current);
+        }
+    }
+
+    // Slugs contain only alphanumerics and hyphens, so no LIKE escaping is needed
+    let mut stmt = conn
+        .prepare("SELECT slug FROM specfiles WHERE (slug = ?1 OR slug LIKE ?2) AND id IS NOT ?3")?;
+    let taken = stmt
+        .query_map(params![base, format!("{}-%", base), id], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(slug::first_free(&base, |candidate| {
+        taken.iter().any(|slug| slug == candidate)
+    }))
+}
+
+/// Schema version created by this version of the library
+///
+/// The version of a database is stored in `PRAGMA user_version` and
+/// raised by the migrations that run when the database is opened. It is
+/// also the newest version this library can open.
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// A migration step; the flag is the builder's `autoincrement` option
+type Migration = fn(&Connection, bool) -> Result<()>;
+
+/// Migration steps in order: `MIGRATIONS[n]` upgrades a database from version `n` to `n + 1`
+///
+/// Append new steps to the end and never change released ones.
+const MIGRATIONS: &[Migration] = &[
+    migrate_baseline,
+    migrate_tags,
+    migrate_fts,
+    migrate_trash,
+    migrate_undo_journal,
+];
+
+/// Brings the schema up to [`SCHEMA_VERSION`] by applying the missing migration steps
+///
+/// Each step runs in its own transaction together with the version bump,
+/// so an interrupted upgrade resumes with the step that failed. Databases
+/// with a newer version than this library knows are rejected with
+/// [`SpecError::UnsupportedSchemaVersion`] before anything is written.
+fn run_migrations(conn: &Connection, autoincrement: bool) -> Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(SpecError::UnsupportedSchemaVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        }
+        .into());
+    }
+    for (step, migrate) in MIGRATIONS.iter().enumerate().skip(version.max(0) as usize) {
+        let tx = conn.unchecked_transaction()?;
+        migrate(&tx, autoincrement)?;
+        tx.pragma_update(None, "user_version", step as i64 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Version 1: the `specfiles` table as it was when schema versions were introduced
+///
+/// Databases created before then have no version but may lack some of the
+/// columns that were added over time, so missing ones are added here.
+fn migrate_baseline(conn: &Connection, autoincrement: bool) -> Result<()> {
+    let id_column = if autoincrement {
+        "id INTEGER PRIMARY KEY AUTOINCREMENT"
+    } else {
+        "id INTEGER PRIMARY KEY"
+    };
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS specfiles (
+                {id_column},
+                name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                content TEXT NOT NULL,
+                slug TEXT,
+                format_version INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT,
+                updated_at TEXT,
+                metadata_json TEXT {METADATA_JSON_CHECK}
+            )"
+        ),
+        [],
+    )?;
+    add_column_if_missing(conn, "specfiles", "slug", "TEXT")?;
+    add_column_if_missing(
+        conn,
+        "specfiles",
+        "format_version",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    // Rows that predate timestamps get the time of the upgrade
+    for column in ["created_at", "updated_at"] {
+        if add_column_if_missing(conn, "specfiles", column, "TEXT")? {
+            conn.execute(
+                &format!("UPDATE specfiles SET {column} = {NOW_RFC3339}"),
+                [],
+            )?;
+        }
+    }
+    add_column_if_missing(
+        conn,
+        "specfiles",
+        "metadata_json",
+        &format!("TEXT {METADATA_JSON_CHECK}"),
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_specfiles_slug ON specfiles (slug)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Version 2: tags, stored once in `tags` and linked to specfiles by `specfile_tags`
+fn migrate_tags(conn: &Connection, _autoincrement: bool) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE specfile_tags (
+            specfile_id INTEGER NOT NULL REFERENCES specfiles (id),
+            tag_id INTEGER NOT NULL REFERENCES tags (id),
+            PRIMARY KEY (specfile_id, tag_id)
+        );
+        CREATE INDEX idx_specfile_tags_tag ON specfile_tags (tag_id);",
+    )?;
+    Ok(())
+}
+
+/// Version 3: the `specfiles_fts` full-text index, kept in sync by triggers
+///
+/// The index is an external-content FTS5 table, so it stores no copy of
+/// the text itself. Existing specfiles are indexed by the rebuild.
+fn migrate_fts(conn: &Connection, _autoincrement: bool) -> Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE specfiles_fts USING fts5(
+            name, description, content,
+            content = 'specfiles', content_rowid = 'id'
+        );
+        CREATE TRIGGER specfiles_fts_insert AFTER INSERT ON specfiles BEGIN
+            INSERT INTO specfiles_fts (rowid, name, description, content)
+            VALUES (new.id, new.name, new.description, new.content);
+        END;
+        CREATE TRIGGER specfiles_fts_delete AFTER DELETE ON specfiles BEGIN
+            INSERT INTO specfiles_fts (specfiles_fts, rowid, name, description, content)
+            VALUES ('delete', old.id, old.name, old.description, old.content);
+        END;
+        CREATE TRIGGER specfiles_fts_update AFTER UPDATE OF name, description, content
+        ON specfiles BEGIN
+            INSERT INTO specfiles_fts (specfiles_fts, rowid, name, description, content)
+            VALUES ('delete', old.id, old.name, old.description, old.content);
+            INSERT INTO specfiles_fts (rowid, name, description, content)
+            VALUES (new.id, new.name, new.description, new.content);
+        END;
+        INSERT INTO specfiles_fts (specfiles_fts) VALUES ('rebuild');",
+    )?;
+    Ok(())
+}
+
+/// Version 4: the `deleted_at` column, set while a specfile is in the trash
+fn migrate_trash(conn: &Connection, _autoincrement: bool) -> Result<()> {
+    conn.execute("ALTER TABLE specfiles ADD COLUMN deleted_at TEXT", [])?;
+    Ok(())
+}
+
+/// Version 5: the `undo_journal` holding the rows before the last destructive operation
+///
+/// Any other change to `specfiles` empties the journal, so stale snapshots
+/// can't overwrite newer edits.
+fn migrate_undo_journal(conn: &Connection, _autoincrement: bool) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE undo_journal (
+            snapshot_json TEXT NOT NULL
+        );
+        CREATE TRIGGER undo_journal_expire_insert AFTER INSERT ON specfiles BEGIN
+            DELETE FROM undo_journal;
+        END;
+        CREATE TRIGGER undo_journal_expire_update AFTER UPDATE ON specfiles BEGIN
+            DELETE FROM undo_journal;
+        END;
+        CREATE TRIGGER undo_journal_expire_delete AFTER DELETE ON specfiles BEGIN
+            DELETE FROM undo_journal;
+        END;",
+    )?;
+    Ok(())
+}
+
+/// Returns true if `table` has a column named `column`
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let exists = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    Ok(exists)
+}
+
+/// Adds a column to an existing table unless it is already present
+///
+/// Returns true if the column was added.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool> {
+    let exists = has_column(conn, table, column)?;
+    if !exists {
+        conn.execute(
+            &format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                quote_identifier(table),
+                quote_identifier(column),
+                definition
+            ),
+            [],
+        )?;
+    }
+    Ok(!exists)
+}
+
+/// Subquery collecting the tags of a specfile into a JSON array, for [`tags_from_row`]
+///
+/// Refers to the unaliased `specfiles` table of the outer query.
+macro_rules! tags_column {
+    () => {
+        "(SELECT json_group_array(tags.name ORDER BY tags.name)
+          FROM specfile_tags JOIN tags ON tags.id = specfile_tags.tag_id
+          WHERE specfile_tags.specfile_id = specfiles.id)"
+    };
+}
+
+/// Columns to select for [`specfile_from_row`], in order
+const SPECFILE_COLUMNS: &str = concat!(
+    "id, name, description, content, format_version, created_at, updated_at, ",
+    tags_column!()
+);
+
+/// Columns to select for a [`SpecSummary`], in order
+const SUMMARY_COLUMNS: &str = concat!(
+    "id, name, description, format_version, created_at, updated_at, ",
+    tags_column!()
+);
+
+/// Constraint that keeps the `metadata_json` column well-formed
+const METADATA_JSON_CHECK: &str = "CHECK (metadata_json IS NULL OR json_valid(metadata_json))";
+
+/// Name of the index created by [`SpecBaseBuilder::unique_names`]
+const UNIQUE_NAME_INDEX: &str = "idx_specfiles_name_unique";
+
+/// SQL expression for the current time as RFC 3339 text in UTC
+const NOW_RFC3339: &str = "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')";
+
+/// Maps a row of [`SPECFILE_COLUMNS`] to a Specfile
+fn specfile_from_row(row: &Row) -> rusqlite::Result<Specfile> {
+    Ok(Specfile {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        description: row.get(2)?,
+        content: row.get(3)?,
+        format_version: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        tags: tags_from_row(row, 7)?,
+    })
+}
+
+/// Reads the tags selected by the `tags_column!` subquery at `index`
+fn tags_from_row(row: &Row, index: usize) -> rusqlite::Result<Vec<String>> {
+    serde_json::from_str(&row.get::<_, String>(index)?).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, error.into())
+    })
+}
+
+/// A complete `specfiles` row with its tags, as saved in the undo journal
+#[derive(Serialize, Deserialize)]
+struct UndoRow {
+    id: i64,
+    name: String,
+    description: String,
+    content: String,
+    slug: Option<String>,
+    format_version: i32,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    metadata_json: Option<String>,
+    deleted_at: Option<String>,
+    tags: Vec<String>,
+}
+
+/// Reads the complete rows of the given specfiles, including trashed ones, for [`record_undo`]
+///
+/// IDs without a row are skipped.
+fn snapshot_rows(conn: &Connection, ids: &[i64]) -> Result<Vec<UndoRow>> {
+    let mut stmt = conn.prepare(concat!(
+        "SELECT id, name, description, content, slug, format_version,
+             created_at, updated_at, metadata_json, deleted_at, ",
+        tags_column!(),
+        " FROM specfiles WHERE id = ?1"
+    ))?;
+    let mut rows = Vec::with_capacity(ids.len());
+    for id in ids {
+        let row = stmt
+            .query_row(params![id], |row| {
+                Ok(UndoRow {
+                    id: row.get(0)?,
                     name: row.get(1)?,
                     description: row.get(2)?,
                     content: row.get(3)?,
+                    slug: row.get(4)?,
+                    format_version: row.get(5)?,
+                    created_at: row.get(6)?,
+                    updated_at: row.get(7)?,
+                    metadata_json: row.get(8)?,
+                    deleted_at: row.get(9)?,
+                    tags: tags_from_row(row, 10)?,
                 })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+            })
+            .optional()?;
+        rows.extend(row);
+    }
+    Ok(rows)
+}
 
-        Ok(specfiles)
+/// Replaces the undo journal with `rows`, the state before the current operation
+///
+/// Must run after the operation's own writes, which discard the old journal.
+fn record_undo(conn: &Connection, rows: &[UndoRow]) -> Result<()> {
+    conn.execute("DELETE FROM undo_journal", [])?;
+    conn.execute(
+        "INSERT INTO undo_journal (snapshot_json) VALUES (?1)",
+        params![serde_json::to_string(rows)?],
+    )?;
+    Ok(())
+}
+
+/// Returns the sorted content hashes of all specfiles, grouped by name
+///
+/// Trashed specfiles are skipped. `conn` may be an unmigrated database from
+/// before the trash existed, so the filter is only added if the column does.
+fn content_hashes_by_name(conn: &Connection) -> Result<BTreeMap<String, Vec<u64>>> {
+    let sql = if has_column(conn, "specfiles", "deleted_at")? {
+        "SELECT name, content FROM specfiles WHERE deleted_at IS NULL"
+    } else {
+        "SELECT name, content FROM specfiles"
+    };
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+
+    let mut hashes: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(0)?;
+        let content: String = row.get(1)?;
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        hashes.entry(name).or_default().push(hasher.finish());
+    }
+    for name_hashes in hashes.values_mut() {
+        name_hashes.sort_unstable();
+    }
+
+    Ok(hashes)
+}
+
+/// Quotes an SQL identifier, doubling any embedded double quotes
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Writes a value as an SQL literal
+fn write_sql_literal<W: Write>(value: ValueRef, writer: &mut W) -> Result<()> {
+    match value {
+        ValueRef::Null => write!(writer, "NULL")?,
+        ValueRef::Integer(integer) => write!(writer, "{}", integer)?,
+        ValueRef::Real(real) => write!(writer, "{:?}", real)?,
+        ValueRef::Text(text) => {
+            let text = String::from_utf8_lossy(text);
+            write!(writer, "'{}'", text.replace('\'', "''"))?
+        }
+        ValueRef::Blob(blob) => {
+            write!(writer, "X'")?;
+            for byte in blob {
+                write!(writer, "{:02X}", byte)?;
+            }
+            write!(writer, "'")?
+        }
+    }
+    Ok(())
+}
+
+/// Turns a user query into an FTS5 query requiring all of its words
+///
+/// Each word is quoted so characters like `-` or `:` aren't read as FTS5
+/// operators. Returns `None` if the query has no words.
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"", word))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Builds the `WHERE` condition and its `LIKE` patterns for a search query
+///
+/// A query without any words in `All` or `Any` mode falls back to phrase
+/// matching, where an empty query matches every specfile.
+fn search_condition(query: &str, mode: QueryMode) -> (String, Vec<String>) {
+    let terms: Vec<&str> = match mode {
+        QueryMode::Phrase => vec![query],
+        QueryMode::All | QueryMode::Any => query.split_whitespace().collect(),
+    };
+    if terms.is_empty() {
+        return search_condition(query, QueryMode::Phrase);
+    }
+
+    let separator = if mode == QueryMode::Any {
+        " OR "
+    } else {
+        " AND "
+    };
+    let condition = (1..=terms.len())
+        .map(|n| {
+            format!(
+                "(name LIKE ?{n} ESCAPE '\\' OR description LIKE ?{n} ESCAPE '\\' \
+                 OR content LIKE ?{n} ESCAPE '\\')"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(separator);
+    let patterns = terms
+        .iter()
+        .map(|term| format!("%{}%", escape_like(term)))
+        .collect();
+    (condition, patterns)
+}
+
+/// Escapes `LIKE` wildcards in `text` so it matches literally, using `\` as escape character
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Translates a glob pattern into a `LIKE` pattern that uses `\` as escape character
+fn glob_to_like(pattern: &str) -> String {
+    let mut like = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '*' => like.push('%'),
+            '?' => like.push('_'),
+            '%' | '_' | '\\' => {
+                like.push('\\');
+                like.push(c);
+            }
+            c => like.push(c),
+        }
+    }
+    like
+}
+
+/// Common English words that carry little meaning on their own
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "can", "for", "from", "has", "have",
+    "if", "in", "into", "is", "it", "its", "not", "of", "on", "or", "should", "so", "that", "the",
+    "their", "then", "there", "these", "this", "to", "was", "we", "will", "with", "you",
+];
+
+/// Adds the lowercased words of `content` to `counts`
+fn count_words(content: &str, exclude_stop_words: bool, counts: &mut HashMap<String, u64>) {
+    for word in content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let word = word.to_lowercase();
+        if exclude_stop_words && STOP_WORDS.contains(&word.as_str()) {
+            continue;
+        }
+        *counts.entry(word).or_insert(0) += 1;
+    }
+}
+
+/// Returns the `top_n` most frequent words, ties broken alphabetically
+fn top_words(counts: HashMap<String, u64>, top_n: usize) -> Vec<(String, u64)> {
+    let mut words: Vec<(String, u64)> = counts.into_iter().collect();
+    words.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    words.truncate(top_n);
+    words
+}
+
+/// Returns the index initial of `name`: its uppercased first letter, or '#'
+fn initial(name: &str) -> char {
+    match name.trim_start().chars().next() {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().next().unwrap_or(c),
+        _ => '#',
     }
 }
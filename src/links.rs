@@ -0,0 +1,154 @@
+//! Extraction and validation of links in specfile content
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Characters that end a URL when it appears in markdown text
+const URL_TERMINATORS: &[char] = &[')', '>', ']', '"', '\'', '`', '<'];
+
+/// Returns all `http://` and `https://` URLs found in `content`, in order
+///
+/// Covers inline links (`[text](url)`), autolinks (`<url>`), and bare URLs.
+/// Trailing sentence punctuation is not considered part of a URL.
+pub(crate) fn extract_urls(content: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    let mut rest = content;
+    while let Some(start) = find_scheme(rest) {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || URL_TERMINATORS.contains(&c))
+            .unwrap_or(candidate.len());
+        let url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        urls.push(url);
+        rest = &candidate[end.max(1)..];
+    }
+    urls
+}
+
+/// Scheme of references from one specfile to another, e.g. `spec://42`
+const SPEC_SCHEME: &str = "spec://";
+
+/// Returns the IDs of all `spec://<id>` references in `content`, in order
+///
+/// The ID is the run of digits after the scheme; a `spec://` without
+/// digits, or with more than fit an `i64`, is not a reference.
+pub(crate) fn extract_spec_references(content: &str) -> Vec<i64> {
+    let mut ids = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(SPEC_SCHEME) {
+        rest = &rest[start + SPEC_SCHEME.len()..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if let Ok(id) = rest[..end].parse() {
+            ids.push(id);
+        }
+        rest = &rest[end..];
+    }
+    ids
+}
+
+/// Finds cycles in a graph of references, given as the referenced IDs of each ID
+///
+/// A depth-first search from each ID in ascending order reports one cycle
+/// per reference back to an ID still on the search path, listed from that ID
+/// onwards. Every group of mutually reachable specfiles thus shows up in at
+/// least one cycle, but not every possible cycle through it is listed.
+/// References to IDs that aren't keys of `graph` are ignored.
+pub(crate) fn find_cycles(graph: &BTreeMap<i64, Vec<i64>>) -> Vec<Vec<i64>> {
+    // `true` while an ID is on the search path, `false` once it is finished
+    let mut on_path: HashMap<i64, bool> = HashMap::new();
+    let mut cycles = Vec::new();
+
+    for &start in graph.keys() {
+        if on_path.contains_key(&start) {
+            continue;
+        }
+        on_path.insert(start, true);
+        // Each entry is an ID on the path and the index of its next reference
+        let mut path = vec![(start, 0)];
+
+        while let Some((id, next)) = path.last_mut() {
+            let references = &graph[id];
+            let Some(&target) = references.get(*next) else {
+                on_path.insert(*id, false);
+                path.pop();
+                continue;
+            };
+            *next += 1;
+            if !graph.contains_key(&target) {
+                continue;
+            }
+            match on_path.get(&target) {
+                None => {
+                    on_path.insert(target, true);
+                    path.push((target, 0));
+                }
+                Some(true) => {
+                    let first = path.iter().position(|&(id, _)| id == target).unwrap();
+                    cycles.push(path[first..].iter().map(|&(id, _)| id).collect());
+                }
+                Some(false) => {}
+            }
+        }
+    }
+
+    cycles
+}
+
+/// Returns true if `url` has a plausible host after its scheme
+///
+/// The host must be non-empty, consist of letters, digits, hyphens, and
+/// dots without empty labels, and may be followed by a numeric port.
+pub(crate) fn is_well_formed(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+
+    let valid_port = port.is_none_or(|port| !port.is_empty() && port.parse::<u16>().is_ok());
+    let valid_host = !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        });
+
+    valid_host && valid_port
+}
+
+/// Finds the start of the next `http://` or `https://` in `text`
+fn find_scheme(text: &str) -> Option<usize> {
+    match (text.find("http://"), text.find("https://")) {
+        (Some(http), Some(https)) => Some(http.min(https)),
+        (http, https) => http.or(https),
+    }
+}
+
+/// Returns true if `url` answers with a success or redirect status
+///
+/// Sends a HEAD request and falls back to GET for servers that don't
+/// support HEAD. Connection failures and timeouts count as dead links.
+#[cfg(feature = "net")]
+pub(crate) fn is_reachable(agent: &ureq::Agent, url: &str) -> bool {
+    let status = match agent.head(url).call() {
+        Ok(response) => response.status().as_u16(),
+        Err(_) => return false,
+    };
+    if status == 405 || status == 501 {
+        return match agent.get(url).call() {
+            Ok(response) => response.status().as_u16() < 400,
+            Err(_) => false,
+        };
+    }
+    status < 400
+}
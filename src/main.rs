@@ -1,15 +1,15 @@
 //! SpecBase CLI
-//! 
+//!
 //! A command-line tool for managing specification files in a structured way.
 //! Uses SQLite as a backend database to store and query specifications.
-//! 
+//!
 //! # Usage
-//! 
+//!
 //! Initialize a new database:
 //! ```bash
 //! spec init
 //! ```
-//! 
+//!
 //! Add a new specification:
 //! ```bash
 //! spec add --name "My Spec" --description "Description" --content "# Content"
@@ -17,11 +17,20 @@
 //! spec add --name "My Spec" --description "Description" --file path/to/spec.md
 //! ```
 
-use clap::{Parser, Subcommand};
-use lib_specbase::{SpecBase, Specfile};
+use anstream::ColorChoice;
+use anstyle::Style;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use lib_specbase::{
+    CheckpointMode, QueryMode, SpecBase, SpecError, Specfile, CURRENT_FORMAT_VERSION,
+    DEFAULT_TEMPLATE,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
-use anyhow::{Result, Context};
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Version string from Cargo.toml
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -32,6 +41,36 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 #[command(about = "SpecBase CLI - A tool to manage specification files")]
 #[command(version = VERSION)]
 struct Cli {
+    /// Path of the database file [default: nearest .specbase.toml, then ~/.config/specbase/specbase.db]
+    #[arg(long, global = true, env = "SPECBASE_DB")]
+    db_path: Option<PathBuf>,
+
+    /// When to color the output; `auto` colors terminals unless NO_COLOR is set
+    #[arg(long, global = true, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+
+    /// Output format of `get`, `list`, `query`, and `search`
+    #[arg(long = "format", global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Abort searches and listings that take longer than this many seconds [default: no limit]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// Open the database immutable, so no command can change it; only use this if nothing else writes to it
+    #[arg(long, global = true)]
+    immutable: bool,
+
+    /// The command to execute
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// A command line entered in the REPL, which has no binary name
+#[derive(Parser)]
+#[command(name = "spec", no_binary_name = true)]
+#[command(about = "Commands available in the REPL; type `quit` to exit")]
+struct ReplLine {
     /// The command to execute
     #[command(subcommand)]
     command: Commands,
@@ -40,76 +79,812 @@ struct Cli {
 /// Available commands for the SpecBase CLI
 #[derive(Subcommand)]
 enum Commands {
-    /// Initialize a new spec database in ~/.config/specbase/
-    Init,
-    
+    /// Initialize a new spec database (in ~/.config/specbase/ by default)
+    Init {
+        /// Never reuse IDs of deleted specfiles (only applies to a new database)
+        #[arg(long)]
+        autoincrement: bool,
+    },
+
     /// Add a new specfile to the database
     Add {
         /// Name of the specification
         #[arg(long)]
         name: String,
-        /// Brief description of the specification
+        /// Brief description of the specification [default: empty, or as set in .specbase.toml]
         #[arg(long)]
-        description: String,
+        description: Option<String>,
         /// Content of the specification in markdown format
         #[arg(long)]
         content: Option<String>,
-        /// Path to a file containing the specification content
-        #[arg(long)]
+        /// Path to a file containing the specification content, or `-` for stdin
+        ///
+        /// If neither --content, --file, nor --edit is given, the content is read from stdin.
+        #[arg(long, conflicts_with = "content")]
         file: Option<PathBuf>,
+        /// Write the content in $EDITOR
+        #[arg(long, conflicts_with_all = ["content", "file"])]
+        edit: bool,
+        /// Tag to group the specification by, e.g. "auth"; repeat for several tags
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+
+    /// Create a new specfile from a template with a standard heading structure
+    Scaffold {
+        /// Name of the specification
+        #[arg(long)]
+        name: String,
+        /// Brief description of the specification [default: empty, or as set in .specbase.toml]
+        #[arg(long)]
+        description: Option<String>,
+        /// Path to a custom markdown template; `{name}` and `{description}` are filled in
+        #[arg(long)]
+        template: Option<PathBuf>,
+    },
+
+    /// Copy a specfile as "Copy of <name>" and print the new ID
+    Clone {
+        /// ID of the specfile to copy
+        id: i64,
     },
-    
+
+    /// Split a specfile into new specfiles, one per section at a heading level
+    Split {
+        /// ID of the specfile to split
+        id: i64,
+        /// Heading level to split at, from 1 (`#`) to 6 (`######`)
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=6))]
+        level: u32,
+        /// Move the original specfile to the trash after splitting it
+        #[arg(long)]
+        delete: bool,
+    },
+
     /// Retrieve a specfile by its ID
     Get {
         /// ID of the specfile to retrieve
         id: i64,
+        /// Prefix each line of the content with its line number
+        #[arg(long)]
+        line_numbers: bool,
+        /// Format to print the content in
+        #[arg(long = "as", value_enum, default_value_t = ContentFormat::Markdown)]
+        format: ContentFormat,
+        /// Print the number of words and characters of the content instead of the content
+        #[arg(long, conflicts_with_all = ["line_numbers", "format"])]
+        stats: bool,
     },
-    
-    /// Update an existing specfile
+
+    /// Update an existing specfile; fields that aren't given keep their current value
+    #[command(group(
+        ArgGroup::new("changes")
+            .required(true)
+            .multiple(true)
+            .args(["name", "description", "content", "format_version", "tags", "edit"])
+    ))]
     Update {
         /// ID of the specfile to update
         #[arg(long)]
         id: i64,
         /// New name for the specification
         #[arg(long)]
-        name: String,
+        name: Option<String>,
         /// New description for the specification
         #[arg(long)]
-        description: String,
+        description: Option<String>,
         /// New content for the specification
         #[arg(long)]
-        content: String,
+        content: Option<String>,
+        /// Edit the current content in $EDITOR
+        #[arg(long, conflicts_with = "content")]
+        edit: bool,
+        /// Format version of the new content [default: the specfile's current one]
+        #[arg(long)]
+        format_version: Option<i32>,
+        /// New tags for the specification; repeat for several tags [default: the specfile's current ones]
+        #[arg(long = "tag")]
+        tags: Vec<String>,
     },
-    
-    /// Delete a specfile by its ID
+
+    /// Move a specfile to the trash by its ID
     Delete {
         /// ID of the specfile to delete
         id: i64,
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
     },
-    
+
+    /// Restore a specfile from the trash by its ID
+    Restore {
+        /// ID of the deleted specfile
+        id: i64,
+    },
+
+    /// Show the specfiles in the trash
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+
+    /// Permanently remove all specfiles in the trash
+    Purge {
+        /// Purge without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Undo the most recent delete, purge, or bulk update
+    Undo,
+
     /// List all specfiles in the database
-    List,
-    
+    List {
+        /// Only list specfiles whose name matches this glob pattern (`*` and `?` wildcards)
+        #[arg(long)]
+        name_like: Option<String>,
+        /// Only list specfiles with this tag
+        #[arg(long, conflicts_with = "name_like")]
+        tag: Option<String>,
+        /// Maximum number of specfiles to list, ordered by ID; applied after --name-like or --tag
+        #[arg(long)]
+        limit: Option<u32>,
+        /// Number of specfiles to skip, ordered by ID; applied after --name-like or --tag
+        #[arg(long)]
+        offset: Option<u32>,
+        /// Also print the first N non-empty lines of each specfile's content
+        #[arg(long, value_name = "N")]
+        preview: Option<usize>,
+    },
+
     /// Search for specfiles using fulltext search
     Query {
         /// Search term to look for in names, descriptions, and content
         query: String,
+        /// Print only the IDs of matching specfiles, one per line
+        #[arg(long)]
+        ids_only: bool,
+        /// How to match a query of several words
+        #[arg(long, value_enum, default_value_t = QueryModeArg::Phrase)]
+        mode: QueryModeArg,
+    },
+
+    /// Search specfiles by words using the full-text index, best matches first
+    Search {
+        /// Words that must all appear in the name, description, or content
+        query: String,
+    },
+
+    /// Print the content of the single specfile matching a search term
+    Find {
+        /// Search term to look for in names, descriptions, and content
+        query: String,
+    },
+
+    /// Interactively pick a specfile by fuzzy name search and print its content
+    Pick,
+
+    /// Run commands interactively against a database that stays open
+    Repl,
+
+    /// Show a random sample of specfiles with a short preview, e.g. for review
+    Sample {
+        /// Number of specfiles to show
+        #[arg(long, default_value_t = 5)]
+        n: u32,
+    },
+
+    /// Show the most frequent words across all specfiles, or in a single one
+    Words {
+        /// ID of a specfile to analyze instead of all specfiles
+        id: Option<i64>,
+        /// Number of words to show
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+        /// Also count common words such as "the" and "and"
+        #[arg(long)]
+        include_stop_words: bool,
+    },
+
+    /// Print the lines of a specfile that match a pattern, with line numbers
+    Grep {
+        /// ID of the specfile to search
+        id: i64,
+        /// Text to search for
+        pattern: String,
+        /// Treat the pattern as a regular expression
+        #[arg(long)]
+        regex: bool,
+    },
+
+    /// List specfiles that share tags with a specfile, most shared tags first
+    Related {
+        /// ID of the specfile to find related specfiles for
+        id: i64,
+        /// Maximum number of specfiles to show
+        #[arg(long, default_value_t = 10)]
+        limit: u32,
+    },
+
+    /// Show when specfiles were created and last updated, oldest first
+    Timeline,
+
+    /// Show statistics about tags
+    Tags {
+        #[command(subcommand)]
+        action: TagsAction,
+    },
+
+    /// Compare the spec database with another database file
+    DbDiff {
+        /// Path to the other database file
+        other: PathBuf,
+    },
+
+    /// Show information about the spec database
+    Info {
+        /// Also show the SQLite pragma settings of the connection
+        #[arg(long)]
+        pragmas: bool,
+    },
+
+    /// Write the write-ahead log back into the database file
+    Checkpoint {
+        /// How aggressively to checkpoint
+        #[arg(long, value_enum, default_value_t = CheckpointModeArg::Passive)]
+        mode: CheckpointModeArg,
+    },
+    /// Write the schema and all data as a SQL script
+    Dump {
+        /// File to write the SQL script to; prints to stdout if omitted
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Write every specfile as a markdown file with a frontmatter block
+    Export {
+        /// Directory to write the files to; created if it doesn't exist
+        #[arg(long)]
+        dir: PathBuf,
+    },
+
+    /// Create specfiles from the markdown files in a directory
+    Import {
+        /// Directory containing the `.md` files, e.g. written by `spec export`
+        #[arg(long)]
+        dir: PathBuf,
+    },
+
+    /// Recompute the slugs of all specfiles from their names
+    Reslug,
+
+    /// Show or replace the JSON metadata attached to a specfile
+    Metadata {
+        /// ID of the specfile
+        id: i64,
+        /// JSON document to store instead of printing the current one; `null` removes it
+        #[arg(long)]
+        set: Option<String>,
+    },
+
+    /// Check specfiles for common problems
+    Lint {
+        /// The check to run
+        #[command(subcommand)]
+        check: LintCheck,
     },
 }
 
+/// Actions available for the `tags` command
+#[derive(Subcommand)]
+enum TagsAction {
+    /// Show how many specfiles with a tag were created per day, oldest first
+    Growth {
+        /// The tag to count
+        tag: String,
+    },
+}
+
+/// Actions available for the `trash` command
+#[derive(Subcommand)]
+enum TrashAction {
+    /// List the deleted specfiles and when they were deleted
+    List,
+}
+
+/// Checks available for the `lint` command
+#[derive(Subcommand)]
+enum LintCheck {
+    /// Report malformed (and, with the `net` feature, unreachable) external links
+    Links,
+    /// Report specfiles whose content looks like double-encoded UTF-8 (mojibake)
+    Encoding,
+    /// Report `spec://<id>` references to specfiles that don't exist
+    References,
+    /// Report specfiles whose `spec://<id>` references form a cycle
+    Cycles,
+    /// Report specfiles whose content is empty or shorter than a minimum length
+    Stubs {
+        /// Minimum number of characters of content, ignoring surrounding whitespace
+        #[arg(long, default_value_t = 50)]
+        min: usize,
+    },
+}
+
+/// Checkpoint modes accepted by the `checkpoint` command
+#[derive(Clone, Copy, ValueEnum)]
+enum CheckpointModeArg {
+    /// Copy as many pages as possible without waiting
+    Passive,
+    /// Wait for writers, then copy all pages
+    Full,
+    /// Like full, and also wait for readers so the log restarts
+    Restart,
+    /// Like restart, and also truncate the log file
+    Truncate,
+}
+
+/// Content formats accepted by the `get` command
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ContentFormat {
+    /// The content as stored
+    Markdown,
+    /// reStructuredText (requires the `rst` feature)
+    Rst,
+    /// Confluence storage format (requires the `confluence` feature)
+    Confluence,
+}
+
+/// Query modes accepted by the `query` command
+#[derive(Clone, Copy, ValueEnum)]
+enum QueryModeArg {
+    /// Match the whole query as one exact phrase
+    Phrase,
+    /// Require every word to appear somewhere
+    All,
+    /// Require at least one word to appear
+    Any,
+}
+
+impl From<QueryModeArg> for QueryMode {
+    fn from(mode: QueryModeArg) -> Self {
+        match mode {
+            QueryModeArg::Phrase => QueryMode::Phrase,
+            QueryModeArg::All => QueryMode::All,
+            QueryModeArg::Any => QueryMode::Any,
+        }
+    }
+}
+
+impl From<CheckpointModeArg> for CheckpointMode {
+    fn from(mode: CheckpointModeArg) -> Self {
+        match mode {
+            CheckpointModeArg::Passive => CheckpointMode::Passive,
+            CheckpointModeArg::Full => CheckpointMode::Full,
+            CheckpointModeArg::Restart => CheckpointMode::Restart,
+            CheckpointModeArg::Truncate => CheckpointMode::Truncate,
+        }
+    }
+}
+
+/// Color modes accepted by the `--color` option
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorArg {
+    /// Color terminal output unless NO_COLOR is set
+    Auto,
+    /// Always color, even when piped
+    Always,
+    /// Never color
+    Never,
+}
+
+impl From<ColorArg> for ColorChoice {
+    fn from(color: ColorArg) -> Self {
+        match color {
+            ColorArg::Auto => ColorChoice::Auto,
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+        }
+    }
+}
+
+/// Output formats accepted by the `--format` option
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// JSON: an object for `get`, an array for listings
+    Json,
+    /// Like `json`, but listings are an object keyed by specfile ID
+    JsonMap,
+}
+
+/// Style of specfile IDs in listings
+const ID_STYLE: Style = Style::new().dimmed();
+
+/// Style of specfile names in listings
+const NAME_STYLE: Style = Style::new().bold();
+
+/// Name of the project-local config file, looked up from the working directory upwards
+const PROJECT_CONFIG_FILE: &str = ".specbase.toml";
+
+/// Settings read from a project-local `.specbase.toml`
+#[derive(Default, Deserialize)]
+struct ProjectConfig {
+    /// Database file, relative to the directory containing the config file
+    db_path: Option<PathBuf>,
+    /// Description used when `--description` is omitted
+    default_description: Option<String>,
+    /// Reject new specfiles without an explicit `--description`
+    #[serde(default)]
+    require_description: bool,
+    /// Reject specfiles whose name another specfile already has
+    #[serde(default)]
+    unique_names: bool,
+}
+
+impl ProjectConfig {
+    /// Loads the nearest `.specbase.toml` at or above `start`
+    ///
+    /// Like git's search for `.git`, the walk stops at the first config file
+    /// found. A relative `db_path` is resolved against that file's directory.
+    /// Without a config file, the default settings are returned.
+    fn discover(start: &Path) -> Result<ProjectConfig> {
+        for dir in start.ancestors() {
+            let config_path = dir.join(PROJECT_CONFIG_FILE);
+            if !config_path.is_file() {
+                continue;
+            }
+            let text = fs::read_to_string(&config_path)
+                .with_context(|| format!("Failed to read {}", config_path.display()))?;
+            let mut config: ProjectConfig = toml::from_str(&text)
+                .with_context(|| format!("Failed to parse {}", config_path.display()))?;
+            config.db_path = config.db_path.map(|path| dir.join(path));
+            return Ok(config);
+        }
+        Ok(ProjectConfig::default())
+    }
+
+    /// Returns the description to store when `--description` may have been omitted
+    fn description(&self, description: Option<String>) -> Result<String> {
+        match description {
+            Some(description) => Ok(description),
+            None if self.require_description => {
+                bail!("A description is required by {PROJECT_CONFIG_FILE}; pass --description")
+            }
+            None => Ok(self.default_description.clone().unwrap_or_default()),
+        }
+    }
+}
+
+/// Determines which database file to open
+///
+/// Precedence: `--db-path`, then the `SPECBASE_DB` environment variable
+/// (both handled by clap and passed in as `cli_path`), then the `db_path`
+/// of the nearest `.specbase.toml`, then [`SpecBase::default_path`].
+fn resolve_db_path(cli_path: Option<PathBuf>, config: &ProjectConfig) -> Result<PathBuf> {
+    match cli_path.or_else(|| config.db_path.clone()) {
+        Some(path) => Ok(path),
+        None => SpecBase::default_path(),
+    }
+}
+
+/// Prints the ID, name, and description of a specfile followed by a separator
+fn print_summary(specfile: &Specfile) {
+    print_fields(specfile);
+    println!("---");
+}
+
+/// Prints specfiles as summaries separated by `---`, as a JSON array, or as a JSON object keyed by ID
+fn print_specfiles(specfiles: &[Specfile], output: OutputFormat) -> Result<()> {
+    match output {
+        OutputFormat::Text => specfiles.iter().for_each(print_summary),
+        OutputFormat::Json => print_json(&specfiles)?,
+        OutputFormat::JsonMap => {
+            let by_id: BTreeMap<i64, &Specfile> = specfiles
+                .iter()
+                .filter_map(|specfile| Some((specfile.id?, specfile)))
+                .collect();
+            print_json(&by_id)?
+        }
+    }
+    Ok(())
+}
+
+/// Prints a value as pretty-printed JSON
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints the ID, name, description, format version, and timestamps of a specfile
+fn print_fields(specfile: &Specfile) {
+    anstream::println!("ID: {ID_STYLE}{}{ID_STYLE:#}", specfile.id.unwrap());
+    anstream::println!("Name: {NAME_STYLE}{}{NAME_STYLE:#}", specfile.name);
+    println!("Description: {}", specfile.description);
+    println!("Format version: {}", specfile.format_version);
+    if !specfile.tags.is_empty() {
+        println!("Tags: {}", specfile.tags.join(", "));
+    }
+    if let Some(created_at) = &specfile.created_at {
+        println!("Created: {}", created_at);
+    }
+    if let Some(updated_at) = &specfile.updated_at {
+        println!("Updated: {}", updated_at);
+    }
+}
+
+/// Prefixes every line of `content` with its left-aligned, 1-based line number
+///
+/// Numbers are padded to the width of the largest one so the text lines up.
+/// Every line in the result ends with a newline, whether or not `content`
+/// ends with one.
+fn number_lines(content: &str) -> String {
+    let width = content.lines().count().to_string().len();
+    let mut numbered = String::with_capacity(content.len());
+    for (index, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            numbered.push_str(&format!("{:<width$} |\n", index + 1));
+        } else {
+            numbered.push_str(&format!("{:<width$} | {}\n", index + 1, line));
+        }
+    }
+    numbered
+}
+
+/// Number of content lines shown by the `sample` command
+const SAMPLE_PREVIEW_LINES: usize = 3;
+
+/// Prints the first `max_lines` non-empty lines of a specfile's content, indented
+///
+/// Trailing whitespace is trimmed, and `...` marks content that goes on.
+fn print_preview(specfile: &Specfile, max_lines: usize) {
+    let lines: Vec<&str> = specfile
+        .content
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim_start().is_empty())
+        .collect();
+    for line in lines.iter().take(max_lines) {
+        println!("    {}", line);
+    }
+    if lines.len() > max_lines {
+        println!("    ...");
+    }
+}
+
+/// Maximum number of candidates shown by the `pick` command
+const PICK_LIMIT: usize = 10;
+
+/// Scores how well `query` fuzzy-matches `candidate`, higher is better
+///
+/// Every character of the query must appear in the candidate in the same
+/// order, ignoring case. Consecutive matches and matches near the start of
+/// the candidate score higher. Returns `None` if the query doesn't match.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let offset = candidate[position..]
+            .iter()
+            .position(|&c| c == query_char)?;
+        let index = position + offset;
+        score += match previous {
+            Some(previous) if previous + 1 == index => 5,
+            _ => 1,
+        };
+        if index == 0 {
+            score += 3;
+        }
+        previous = Some(index);
+        position = index + 1;
+    }
+    Some(score * 100 - candidate.len() as i64)
+}
+
+/// Prompts on stdout and reads one trimmed line from stdin
+fn prompt(message: &str) -> Result<String> {
+    print!("{}", message);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Reads commands from stdin and runs them until `quit` or EOF
+///
+/// Lines are split into words like a shell would, so arguments with spaces
+/// can be quoted. Errors are printed and the loop continues.
+fn repl(spec_db: &SpecBase, config: &ProjectConfig, output: OutputFormat) -> Result<()> {
+    println!(
+        "SpecBase {}. Type `help` for commands, `quit` to exit.",
+        VERSION
+    );
+    let stdin = std::io::stdin();
+    loop {
+        print!("spec> ");
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            println!();
+            return Ok(());
+        }
+
+        let words = match split_words(&line) {
+            Ok(words) => words,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                continue;
+            }
+        };
+        match words.first().map(String::as_str) {
+            None => continue,
+            Some("quit" | "exit") => return Ok(()),
+            Some(_) => {}
+        }
+
+        // Help and usage errors are reported by clap as errors, too
+        match ReplLine::try_parse_from(words) {
+            Ok(ReplLine { command }) => {
+                if let Err(error) = run(command, spec_db, config, output) {
+                    eprintln!("Error: {:#}", error);
+                }
+            }
+            Err(error) => error.print()?,
+        }
+    }
+}
+
+/// Splits a command line into words, honoring single and double quotes
+///
+/// Inside double quotes and unquoted, a backslash escapes the next character.
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => {
+                let escaped = chars.next().unwrap_or('\\');
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        bail!("unterminated quote");
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Reads specfile content from stdin until EOF
+///
+/// When stdin is a terminal, a hint on how to finish the input is printed
+/// to stderr first. Piped input is read as-is.
+fn read_content_from_stdin() -> Result<String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("Enter the specfile content. Press Ctrl-D on an empty line to finish:");
+    }
+    let mut content = String::new();
+    stdin.read_to_string(&mut content)?;
+    if content.trim().is_empty() {
+        bail!("No content provided. Use --content, --file, or enter content on stdin");
+    }
+    Ok(content)
+}
+
+/// Field changes requested by `spec update`; `None` and no tags keep the current value
+struct SpecfileChanges {
+    name: Option<String>,
+    description: Option<String>,
+    content: Option<String>,
+    format_version: Option<i32>,
+    tags: Vec<String>,
+}
+
+impl SpecfileChanges {
+    /// Returns `current` with the requested changes applied
+    fn apply_to(self, current: Specfile) -> Specfile {
+        Specfile {
+            name: self.name.unwrap_or(current.name),
+            description: self.description.unwrap_or(current.description),
+            content: self.content.unwrap_or(current.content),
+            format_version: self.format_version.unwrap_or(current.format_version),
+            tags: if self.tags.is_empty() {
+                current.tags
+            } else {
+                self.tags
+            },
+            ..current
+        }
+    }
+}
+
+/// Opens `$EDITOR` on a temporary markdown file holding `initial` and returns the saved text
+///
+/// Falls back to `notepad` on Windows and `vi` elsewhere. `$EDITOR` may
+/// include arguments, e.g. `code --wait`. Fails if the editor can't be
+/// started or exits with a non-zero status, so callers abort without saving.
+fn edit_in_editor(initial: &str) -> Result<String> {
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("EDITOR is empty");
+    };
+
+    // create_new refuses to follow a symlink or reuse an existing file planted
+    // at the predictable path, so a fresh suffix is tried instead
+    let mut attempt = 0u32;
+    let (path, mut file) = loop {
+        let path =
+            std::env::temp_dir().join(format!("spec-edit-{}-{}.md", std::process::id(), attempt));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => break (path, file),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("Failed to create {:?}", path));
+            }
+        }
+    };
+    file.write_all(initial.as_bytes())
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    drop(file);
+    let edited = match std::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+    {
+        Ok(status) if status.success() => {
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))
+        }
+        Ok(status) => Err(anyhow!("Editor exited with {}; nothing was saved", status)),
+        Err(error) => Err(anyhow!("Failed to start editor {:?}: {}", program, error)),
+    };
+    // A leftover file in the temp directory is harmless, so errors are ignored
+    let _ = fs::remove_file(&path);
+    edited
+}
+
 /// Main entry point for the SpecBase CLI
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    
+    ColorChoice::write_global(cli.color.into());
+    let working_dir = std::env::current_dir().context("Failed to get working directory")?;
+    let config = ProjectConfig::discover(&working_dir)?;
+    let db_path = resolve_db_path(cli.db_path, &config)?;
+
     match cli.command {
-        Commands::Init => {
-            let config_dir = dirs::config_dir()
-                .context("Failed to get config directory")?
-                .join("specbase");
-            let db_path = config_dir.join("specbase.db");
-            
+        Commands::Init { autoincrement } => {
+            if cli.immutable {
+                bail!("--immutable can't be used with init");
+            }
             if db_path.exists() {
-                println!("Database already exists at {:?}. Do you want to override it? [y/N]", db_path);
+                println!(
+                    "Database already exists at {:?}. Do you want to override it? [y/N]",
+                    db_path
+                );
                 let mut input = String::new();
                 std::io::stdin().read_line(&mut input)?;
                 if !input.trim().eq_ignore_ascii_case("y") {
@@ -117,94 +892,743 @@ fn main() -> Result<()> {
                     return Ok(());
                 }
             }
-            
-            SpecBase::init()?;
+
+            SpecBase::builder()
+                .path(&db_path)
+                .autoincrement(autoincrement)
+                .unique_names(config.unique_names)
+                .init()?;
             println!("Initialized new spec database at {:?}", db_path);
         }
-        
-        Commands::Add { name, description, content, file } => {
+
+        command => {
+            let spec_db = match open_database(&db_path, cli.timeout, cli.immutable, &config) {
+                Ok(spec_db) => spec_db,
+                Err(e) => match e.downcast_ref::<SpecError>() {
+                    Some(SpecError::DatabaseNotFound(_)) => {
+                        println!("no database found at {:?}; run `spec init`", db_path);
+                        std::process::exit(1);
+                    }
+                    _ => return Err(e),
+                },
+            };
+            if let Commands::Repl = command {
+                repl(&spec_db, &config, cli.output)?;
+            } else if !run(command, &spec_db, &config, cli.output)? {
+                std::process::exit(1);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens the existing database at `db_path`, with a query timeout in seconds if given
+fn open_database(
+    db_path: &Path,
+    timeout: Option<u64>,
+    immutable: bool,
+    config: &ProjectConfig,
+) -> Result<SpecBase> {
+    let mut builder = SpecBase::builder()
+        .path(db_path)
+        .unique_names(config.unique_names);
+    if let Some(seconds) = timeout {
+        builder = builder.query_timeout(Duration::from_secs(seconds));
+    }
+    if immutable {
+        builder.open_immutable()
+    } else {
+        builder.open_existing()
+    }
+}
+
+/// Runs a command against an open database
+///
+/// Returns `Ok(false)` if the command failed in a way that should end the
+/// process with a non-zero exit status, e.g. because nothing was found.
+fn run(
+    command: Commands,
+    spec_db: &SpecBase,
+    config: &ProjectConfig,
+    output: OutputFormat,
+) -> Result<bool> {
+    match command {
+        Commands::Init { .. } | Commands::Repl => {
+            bail!("this command can't be run from the REPL")
+        }
+
+        Commands::Add {
+            name,
+            description,
+            content,
+            file,
+            edit,
+            tags,
+        } => {
+            let description = config.description(description)?;
             let content = if let Some(file_path) = file {
-                fs::read_to_string(file_path)?
+                if file_path.as_os_str() == "-" {
+                    read_content_from_stdin()?
+                } else {
+                    fs::read_to_string(file_path)?
+                }
+            } else if let Some(content) = content {
+                content
+            } else if edit {
+                let content = edit_in_editor("")?;
+                if content.trim().is_empty() {
+                    bail!("No content written in the editor; nothing was saved");
+                }
+                content
             } else {
-                content.context("Either --content or --file must be provided")?
+                read_content_from_stdin()?
             };
-            
+
             let specfile = Specfile {
                 id: None,
                 name,
                 description,
                 content,
+                format_version: CURRENT_FORMAT_VERSION,
+                created_at: None,
+                updated_at: None,
+                tags,
+            };
+
+            let id = spec_db.create_specfile(&specfile)?;
+            println!("Added new specfile with ID: {}", id);
+        }
+
+        Commands::Scaffold {
+            name,
+            description,
+            template,
+        } => {
+            let description = config.description(description)?;
+            let template = match template {
+                Some(path) => fs::read_to_string(path)?,
+                None => DEFAULT_TEMPLATE.to_string(),
             };
-            
-            let spec_db = SpecBase::init()?;
+            let specfile = Specfile::scaffold(&name, &description, &template);
+
             let id = spec_db.create_specfile(&specfile)?;
             println!("Added new specfile with ID: {}", id);
         }
-        
-        Commands::Get { id } => {
-            let spec_db = SpecBase::init()?;
-            match spec_db.read_specfile(id) {
-                Ok(specfile) => println!("{}", specfile.content),
-                Err(_) => println!("specfile does not exist"),
+
+        Commands::Clone { id } => match spec_db.clone_specfile(id) {
+            Ok(new_id) => println!("Added new specfile with ID: {}", new_id),
+            Err(e) => match e.downcast_ref::<SpecError>() {
+                Some(SpecError::SpecfileNotFound(_)) => println!("specfile does not exist"),
+                _ => return Err(e),
+            },
+        },
+
+        Commands::Split { id, level, delete } => {
+            let ids = match spec_db.split_by_heading(id, level, delete) {
+                Ok(ids) => ids,
+                Err(e) => match e.downcast_ref::<SpecError>() {
+                    Some(SpecError::SpecfileNotFound(_)) => {
+                        println!("specfile does not exist");
+                        return Ok(true);
+                    }
+                    _ => return Err(e),
+                },
+            };
+            if ids.is_empty() {
+                println!("no headings of level {} found", level);
+                return Ok(true);
+            }
+            for new_id in ids {
+                println!("Added new specfile with ID: {}", new_id);
+            }
+            if delete {
+                println!("Moved specfile {} to the trash", id);
             }
         }
-        
-        Commands::Update { id, name, description, content } => {
-            let specfile = Specfile {
-                id: Some(id),
+
+        Commands::Get {
+            id,
+            line_numbers,
+            format,
+            stats,
+        } => {
+            if output != OutputFormat::Text {
+                if line_numbers || format != ContentFormat::Markdown || stats {
+                    bail!("--line-numbers, --as, and --stats only apply to text output");
+                }
+                let Some(specfile) = spec_db.get_specfile(id)? else {
+                    // Keep stdout valid JSON (empty) for whatever parses it
+                    eprintln!("specfile does not exist");
+                    return Ok(false);
+                };
+                print_json(&specfile)?;
+                return Ok(true);
+            }
+            if stats {
+                let Some(specfile) = spec_db.get_specfile(id)? else {
+                    println!("specfile does not exist");
+                    return Ok(false);
+                };
+                println!("Words: {}", specfile.word_count());
+                println!("Characters: {}", specfile.char_count());
+                return Ok(true);
+            }
+            let content = match format {
+                ContentFormat::Markdown => {
+                    spec_db.read_specfile(id).map(|specfile| specfile.content)
+                }
+                #[cfg(feature = "rst")]
+                ContentFormat::Rst => spec_db
+                    .read_specfile_rst(id)
+                    .map(|rst| rst.trim_end().to_string()),
+                #[cfg(not(feature = "rst"))]
+                ContentFormat::Rst => {
+                    bail!("reStructuredText output is not available; rebuild spec with `--features rst`")
+                }
+                #[cfg(feature = "confluence")]
+                ContentFormat::Confluence => spec_db
+                    .read_specfile_confluence(id)
+                    .map(|xhtml| xhtml.trim_end().to_string()),
+                #[cfg(not(feature = "confluence"))]
+                ContentFormat::Confluence => {
+                    bail!("Confluence output is not available; rebuild spec with `--features confluence`")
+                }
+            };
+            match content {
+                Ok(content) if line_numbers => print!("{}", number_lines(&content)),
+                Ok(content) => println!("{}", content),
+                Err(e) => match e.downcast_ref::<SpecError>() {
+                    Some(SpecError::SpecfileNotFound(_)) => {
+                        println!("specfile does not exist");
+                        return Ok(false);
+                    }
+                    _ => return Err(e),
+                },
+            }
+        }
+
+        Commands::Update {
+            id,
+            name,
+            description,
+            content,
+            edit,
+            format_version,
+            tags,
+        } => {
+            let changes = SpecfileChanges {
                 name,
                 description,
                 content,
+                format_version,
+                tags,
             };
-            
-            let spec_db = SpecBase::init()?;
-            match spec_db.update_specfile(id, &specfile) {
-                Ok(_) => println!("ok"),
-                Err(e) => {
-                    if e.to_string().contains("not found") {
-                        println!("specfile does not exist");
-                    } else {
-                        println!("error");
+            let result = if edit {
+                match spec_db.read_specfile(id) {
+                    Ok(current) => {
+                        let version = current.version();
+                        let content = edit_in_editor(&current.content)?;
+                        let specfile = SpecfileChanges {
+                            content: Some(content),
+                            ..changes
+                        }
+                        .apply_to(current);
+                        // Don't overwrite changes made while the editor was open
+                        spec_db.update_specfile_checked(id, &specfile, version)
                     }
+                    Err(error) => Err(error),
+                }
+            } else if changes.format_version.is_none() && changes.tags.is_empty() {
+                spec_db.update_specfile_fields(
+                    id,
+                    changes.name.as_deref(),
+                    changes.description.as_deref(),
+                    changes.content.as_deref(),
+                )
+            } else {
+                // The format version and tags aren't part of a partial update,
+                // so they are merged into the current specfile instead
+                match spec_db.get_specfile(id)? {
+                    Some(current) => spec_db.update_specfile(id, &changes.apply_to(current)),
+                    None => Err(SpecError::SpecfileNotFound(id).into()),
                 }
+            };
+
+            match result {
+                Ok(_) => println!("ok"),
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<SpecError>(),
+                        Some(SpecError::ConflictDetected { .. })
+                    ) =>
+                {
+                    println!("specfile was changed while editing; nothing was saved");
+                    return Ok(false);
+                }
+                Err(e) => match e.downcast_ref::<SpecError>() {
+                    Some(SpecError::SpecfileNotFound(_)) => println!("specfile does not exist"),
+                    _ => return Err(e),
+                },
             }
         }
-        
-        Commands::Delete { id } => {
-            let spec_db = SpecBase::init()?;
+
+        Commands::Delete { id, yes } => {
+            let Some(specfile) = spec_db.get_specfile(id)? else {
+                println!("specfile does not exist");
+                return Ok(true);
+            };
+            if !yes {
+                println!("Delete spec '{}' (ID {})? [y/N]", specfile.name, id);
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("Operation aborted");
+                    return Ok(true);
+                }
+            }
             match spec_db.delete_specfile(id) {
                 Ok(_) => println!("ok"),
-                Err(_) => println!("specfile does not exist"),
+                Err(e) => match e.downcast_ref::<SpecError>() {
+                    Some(SpecError::SpecfileNotFound(_)) => println!("specfile does not exist"),
+                    _ => return Err(e),
+                },
+            }
+        }
+
+        Commands::Restore { id } => match spec_db.restore_specfile(id) {
+            Ok(_) => println!("ok"),
+            Err(e) => match e.downcast_ref::<SpecError>() {
+                Some(SpecError::SpecfileNotFound(_)) => println!("specfile is not in the trash"),
+                _ => return Err(e),
+            },
+        },
+
+        Commands::Trash {
+            action: TrashAction::List,
+        } => {
+            for (specfile, deleted_at) in spec_db.list_deleted()? {
+                anstream::println!(
+                    "{ID_STYLE}{}{ID_STYLE:#} {NAME_STYLE}{}{NAME_STYLE:#} (deleted {})",
+                    specfile.id.unwrap(),
+                    specfile.name,
+                    deleted_at
+                );
+            }
+        }
+
+        Commands::Purge { yes } => {
+            let count = spec_db.list_deleted()?.len();
+            if count == 0 {
+                println!("trash is empty");
+                return Ok(true);
+            }
+            if !yes {
+                println!("Permanently remove {} specs in the trash? [y/N]", count);
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("Operation aborted");
+                    return Ok(true);
+                }
             }
+            let purged = spec_db.purge_deleted()?;
+            println!("Purged {} specs", purged);
         }
-        
-        Commands::List => {
-            let spec_db = SpecBase::init()?;
-            match spec_db.list_specfiles() {
+
+        Commands::Undo => match spec_db.undo_last() {
+            Ok(_) => println!("ok"),
+            Err(e) => match e.downcast_ref::<SpecError>() {
+                Some(SpecError::NothingToUndo) => println!("nothing to undo"),
+                _ => return Err(e),
+            },
+        },
+
+        Commands::List {
+            name_like,
+            tag,
+            limit,
+            offset,
+            preview,
+        } => {
+            let filtered = match (name_like, tag) {
+                (Some(pattern), _) => Some(spec_db.read_by_name_glob(&pattern)),
+                (None, Some(tag)) => Some(spec_db.list_specfiles_by_tag(&tag)),
+                (None, None) => None,
+            };
+            let specfiles = match filtered {
+                // Both filters order by ID, so the page is cut from the matches
+                Some(specfiles) => specfiles.map(|specfiles| {
+                    specfiles
+                        .into_iter()
+                        .skip(offset.unwrap_or(0) as usize)
+                        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+                        .collect()
+                }),
+                None if limit.is_some() || offset.is_some() => {
+                    spec_db.list_specfiles_paged(limit.unwrap_or(u32::MAX), offset.unwrap_or(0))
+                }
+                None => spec_db.list_specfiles(),
+            };
+            if output != OutputFormat::Text {
+                if preview.is_some() {
+                    bail!("--preview only applies to text output");
+                }
+                print_specfiles(&specfiles?, output)?;
+                return Ok(true);
+            }
+            match specfiles {
                 Ok(specfiles) => {
                     for specfile in specfiles {
-                        println!("ID: {}", specfile.id.unwrap());
-                        println!("Name: {}", specfile.name);
-                        println!("Description: {}", specfile.description);
-                        println!("---");
+                        match preview {
+                            Some(max_lines) => {
+                                print_fields(&specfile);
+                                print_preview(&specfile, max_lines);
+                                println!("---");
+                            }
+                            None => print_summary(&specfile),
+                        }
                     }
                     println!("ok");
                 }
-                Err(_) => println!("specfile does not exist"),
+                Err(e) => match e.downcast_ref::<SpecError>() {
+                    Some(SpecError::SpecfileNotFound(_)) => println!("specfile does not exist"),
+                    _ => return Err(e),
+                },
             }
         }
-        
-        Commands::Query { query } => {
-            let spec_db = SpecBase::init()?;
+
+        Commands::Query {
+            query,
+            ids_only,
+            mode,
+        } => {
+            if ids_only {
+                let ids = spec_db.query_ids_with_mode(&query, mode.into())?;
+                match output {
+                    OutputFormat::Text => ids.iter().for_each(|id| println!("{}", id)),
+                    OutputFormat::Json | OutputFormat::JsonMap => print_json(&ids)?,
+                }
+                return Ok(true);
+            }
+            let specfiles = spec_db.query_specfiles_with_mode(&query, mode.into())?;
+            print_specfiles(&specfiles, output)?;
+        }
+
+        Commands::Search { query } => {
+            print_specfiles(&spec_db.search_specfiles(&query)?, output)?;
+        }
+
+        Commands::Find { query } => {
             let specfiles = spec_db.query_specfiles(&query)?;
-            for specfile in specfiles {
-                println!("ID: {}", specfile.id.unwrap());
-                println!("Name: {}", specfile.name);
-                println!("Description: {}", specfile.description);
+            match specfiles.as_slice() {
+                [] => {
+                    println!("specfile does not exist");
+                    return Ok(false);
+                }
+                [specfile] => println!("{}", specfile.content),
+                _ => {
+                    println!(
+                        "{} specfiles match, please refine the search:",
+                        specfiles.len()
+                    );
+                    for specfile in &specfiles {
+                        print_summary(specfile);
+                    }
+                }
+            }
+        }
+
+        Commands::Pick => {
+            let specfiles = spec_db.list_specfiles()?;
+            if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+                for specfile in &specfiles {
+                    print_summary(specfile);
+                }
+                return Ok(true);
+            }
+
+            let query = prompt("Search: ")?;
+            let mut matches: Vec<(i64, &Specfile)> = specfiles
+                .iter()
+                .filter_map(|specfile| {
+                    fuzzy_score(&query, &specfile.name).map(|score| (score, specfile))
+                })
+                .collect();
+            matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            matches.truncate(PICK_LIMIT);
+
+            let selected = match matches.as_slice() {
+                [] => {
+                    println!("specfile does not exist");
+                    return Ok(false);
+                }
+                [(_, specfile)] => specfile,
+                _ => {
+                    for (index, (_, specfile)) in matches.iter().enumerate() {
+                        println!(
+                            "{:>2}) {} (ID: {})",
+                            index + 1,
+                            specfile.name,
+                            specfile.id.unwrap()
+                        );
+                    }
+                    let choice = prompt(&format!("Select [1-{}]: ", matches.len()))?;
+                    match choice
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|n| matches.get(n))
+                    {
+                        Some((_, specfile)) => specfile,
+                        None => {
+                            println!("Operation aborted");
+                            return Ok(true);
+                        }
+                    }
+                }
+            };
+            println!("{}", selected.content);
+        }
+
+        Commands::Sample { n } => {
+            for specfile in spec_db.sample(n)? {
+                print_fields(&specfile);
+                print_preview(&specfile, SAMPLE_PREVIEW_LINES);
                 println!("---");
             }
         }
+
+        Commands::Words {
+            id,
+            top,
+            include_stop_words,
+        } => {
+            let words = match id {
+                Some(id) => spec_db.word_frequencies(id, top, !include_stop_words)?,
+                None => spec_db.vocabulary(top, !include_stop_words)?,
+            };
+            for (word, count) in words {
+                println!("{}: {}", word, count);
+            }
+        }
+
+        Commands::Grep { id, pattern, regex } => {
+            let matches = spec_db.grep_in_specfile(id, &pattern, regex)?;
+            for (number, line) in &matches {
+                println!("{}: {}", number, line);
+            }
+            // Like grep, exit with a non-zero status if nothing matched
+            if matches.is_empty() {
+                return Ok(false);
+            }
+        }
+
+        Commands::Related { id, limit } => {
+            for (specfile, shared) in spec_db.related_by_tags(id, limit)? {
+                anstream::println!(
+                    "{ID_STYLE}{}{ID_STYLE:#} {NAME_STYLE}{}{NAME_STYLE:#} ({} shared tags)",
+                    specfile.id.unwrap(),
+                    specfile.name,
+                    shared
+                );
+            }
+        }
+
+        Commands::Timeline => {
+            // Group events by day: a date heading, then one indented line per event
+            let mut current_day = None;
+            for entry in spec_db.timeline()? {
+                let (day, time) = entry
+                    .timestamp
+                    .split_once('T')
+                    .unwrap_or((entry.timestamp.as_str(), ""));
+                if current_day != Some(day.to_string()) {
+                    println!("{}", day);
+                    current_day = Some(day.to_string());
+                }
+                anstream::println!(
+                    "  {} {} {ID_STYLE}#{}{ID_STYLE:#} {NAME_STYLE}{}{NAME_STYLE:#}",
+                    time.trim_end_matches('Z'),
+                    entry.event,
+                    entry.specfile_id,
+                    entry.name
+                );
+            }
+        }
+
+        Commands::Tags {
+            action: TagsAction::Growth { tag },
+        } => {
+            let mut total = 0;
+            for (day, created) in spec_db.tag_growth(&tag)? {
+                total += created;
+                println!("{}  +{} ({} total)", day, created, total);
+            }
+        }
+
+        Commands::DbDiff { other } => {
+            let diff = spec_db.diff_database(&other)?;
+            if diff.is_empty() {
+                println!("no differences");
+                return Ok(true);
+            }
+
+            let sections = [
+                ("Only in this database", &diff.only_in_self),
+                ("Only in the other database", &diff.only_in_other),
+                ("Content differs", &diff.changed),
+            ];
+            for (title, names) in sections {
+                println!("{} ({}):", title, names.len());
+                for name in names {
+                    println!("  {}", name);
+                }
+            }
+        }
+
+        Commands::Info { pragmas } => {
+            println!("Database: {}", spec_db.path().unwrap_or("in-memory"));
+            println!("Specfiles: {}", spec_db.count_specfiles()?);
+            if pragmas {
+                let info = spec_db.pragmas()?;
+                println!("journal_mode: {}", info.journal_mode);
+                println!("synchronous: {}", info.synchronous);
+                println!("busy_timeout: {}", info.busy_timeout);
+                println!("cache_size: {}", info.cache_size);
+                println!("page_size: {}", info.page_size);
+            }
+        }
+
+        Commands::Checkpoint { mode } => {
+            let result = spec_db.checkpoint(mode.into())?;
+            if result.log_pages < 0 {
+                println!("database is not in WAL mode, nothing to checkpoint");
+            } else {
+                println!("Pages written: {}", result.checkpointed_pages);
+                println!("Pages remaining: {}", result.remaining_pages());
+                if result.busy {
+                    println!("checkpoint incomplete, the database is busy");
+                } else {
+                    println!("ok");
+                }
+            }
+        }
+
+        Commands::Dump { output } => match output {
+            Some(path) => {
+                let file = fs::File::create(&path)?;
+                spec_db.dump_sql(std::io::BufWriter::new(file))?;
+                println!("Wrote SQL dump to {:?}", path);
+            }
+            None => spec_db.dump_sql(std::io::stdout().lock())?,
+        },
+
+        Commands::Export { dir } => {
+            let count = spec_db.export_to_dir(&dir)?;
+            println!("Exported {} specfiles to {:?}", count, dir);
+        }
+
+        Commands::Import { dir } => {
+            let ids = spec_db.import_from_dir(&dir)?;
+            println!("Imported {} specfiles from {:?}", ids.len(), dir);
+        }
+
+        Commands::Reslug => {
+            let changed = spec_db.regenerate_slugs()?;
+            println!("Updated {} slugs", changed);
+        }
+
+        Commands::Metadata { id, set } => match set {
+            Some(json) => {
+                let value: serde_json::Value =
+                    serde_json::from_str(&json).context("Metadata is not valid JSON")?;
+                spec_db.set_metadata_json(id, &value)?;
+                println!("ok");
+            }
+            None => match spec_db.get_metadata_json(id)? {
+                Some(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+                None => println!("no metadata"),
+            },
+        },
+
+        Commands::Lint { check } => match check {
+            LintCheck::Links => {
+                let broken = spec_db.find_broken_links()?;
+                if broken.is_empty() {
+                    println!("ok");
+                    return Ok(true);
+                }
+                for (id, urls) in broken {
+                    println!("ID: {}", id);
+                    for url in urls {
+                        println!("  {}", url);
+                    }
+                }
+                return Ok(false);
+            }
+            LintCheck::Encoding => {
+                let suspicious = spec_db.find_suspicious_encoding()?;
+                if suspicious.is_empty() {
+                    println!("ok");
+                    return Ok(true);
+                }
+                for id in suspicious {
+                    println!("ID: {}", id);
+                }
+                return Ok(false);
+            }
+            LintCheck::References => {
+                let dangling = spec_db.validate_all_references()?;
+                let trashed = spec_db.find_trashed_references()?;
+                if dangling.is_empty() && trashed.is_empty() {
+                    println!("ok");
+                    return Ok(true);
+                }
+                for (id, missing) in &dangling {
+                    println!("ID: {}", id);
+                    for reference in missing {
+                        println!("  spec://{}", reference);
+                    }
+                }
+                // Trashed targets can be restored, so they don't fail the check
+                for (id, references) in trashed {
+                    println!("ID: {}", id);
+                    for reference in references {
+                        println!("  spec://{} (in trash)", reference);
+                    }
+                }
+                return Ok(dangling.is_empty());
+            }
+            LintCheck::Cycles => {
+                let cycles = spec_db.find_cycles()?;
+                if cycles.is_empty() {
+                    println!("ok");
+                    return Ok(true);
+                }
+                for cycle in cycles {
+                    let path: Vec<String> = cycle
+                        .iter()
+                        .chain(cycle.first())
+                        .map(|id| id.to_string())
+                        .collect();
+                    println!("{}", path.join(" -> "));
+                }
+                return Ok(false);
+            }
+            LintCheck::Stubs { min } => {
+                let stubs = spec_db.find_stubs(min)?;
+                if stubs.is_empty() {
+                    println!("ok");
+                    return Ok(true);
+                }
+                for specfile in stubs {
+                    println!("ID: {} ({})", specfile.id.unwrap(), specfile.name);
+                }
+                return Ok(false);
+            }
+        },
     }
-    
-    Ok(())
+
+    Ok(true)
 }
@@ -0,0 +1,213 @@
+//! Conversion between specfiles and markdown files with a frontmatter block
+//!
+//! A specfile is written as a YAML frontmatter block holding its metadata,
+//! followed by the content verbatim:
+//!
+//! ```text
+//! ---
+//! id: 1
+//! name: "Example"
+//! description: "An example spec"
+//! format_version: 1
+//! tags: ["auth", "billing"]
+//! ---
+//! # Example
+//! ```
+//!
+//! Only the small subset of YAML needed for flat `key: value` pairs is
+//! supported: plain, single-quoted, and double-quoted scalars, and flow
+//! sequences of them for the tags.
+
+use crate::slug::slugify;
+use crate::{Specfile, CURRENT_FORMAT_VERSION};
+
+/// Renders a specfile as markdown with its metadata in a frontmatter block
+pub(crate) fn to_markdown(specfile: &Specfile) -> String {
+    let mut markdown = String::with_capacity(specfile.content.len() + 128);
+    markdown.push_str("---\n");
+    if let Some(id) = specfile.id {
+        markdown.push_str(&format!("id: {}\n", id));
+    }
+    markdown.push_str(&format!("name: {}\n", quote(&specfile.name)));
+    markdown.push_str(&format!("description: {}\n", quote(&specfile.description)));
+    markdown.push_str(&format!("format_version: {}\n", specfile.format_version));
+    if !specfile.tags.is_empty() {
+        let tags: Vec<String> = specfile.tags.iter().map(|tag| quote(tag)).collect();
+        markdown.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    }
+    markdown.push_str("---\n");
+    markdown.push_str(&specfile.content);
+    markdown
+}
+
+/// Parses a markdown file into a specfile that has not been saved yet
+///
+/// The name is taken from the frontmatter, then from the first top-level
+/// heading, then from `fallback_name` (usually the file name). The
+/// description defaults to an empty string, the format version to
+/// [`CURRENT_FORMAT_VERSION`], and the tags to none. An `id` in the frontmatter is ignored, since
+/// IDs are assigned by the database on import. Files without frontmatter are
+/// imported with their whole text as content.
+pub(crate) fn from_markdown(text: &str, fallback_name: &str) -> Specfile {
+    let (frontmatter, content) = split_frontmatter(text);
+
+    let mut name = None;
+    let mut description = None;
+    let mut format_version = None;
+    let mut tags = Vec::new();
+    for line in frontmatter.unwrap_or_default().lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "name" => name = Some(unquote(value.trim())),
+            "description" => description = Some(unquote(value.trim())),
+            "format_version" => format_version = unquote(value.trim()).parse().ok(),
+            "tags" => tags = unquote_sequence(value.trim()),
+            _ => {}
+        }
+    }
+
+    let name = name
+        .filter(|name| !name.is_empty())
+        .or_else(|| first_heading(content))
+        .unwrap_or_else(|| fallback_name.to_string());
+
+    Specfile {
+        id: None,
+        name,
+        description: description.unwrap_or_default(),
+        content: content.to_string(),
+        format_version: format_version.unwrap_or(CURRENT_FORMAT_VERSION),
+        created_at: None,
+        updated_at: None,
+        tags,
+    }
+}
+
+/// Returns the file name a specfile is exported under: `<id>-<slug>.md`
+pub(crate) fn file_name(specfile: &Specfile) -> String {
+    let slug = slugify(&specfile.name);
+    match (specfile.id, slug.is_empty()) {
+        (Some(id), false) => format!("{}-{}.md", id, slug),
+        (Some(id), true) => format!("{}.md", id),
+        (None, false) => format!("{}.md", slug),
+        (None, true) => "specfile.md".to_string(),
+    }
+}
+
+/// Splits `text` into its frontmatter block (if any) and the remaining body
+fn split_frontmatter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, text);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
+
+/// Returns the text of the first `# ` heading in `content`
+fn first_heading(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|heading| heading.trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+/// Writes `value` as a double-quoted YAML scalar
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Reads a YAML flow sequence such as `["a", 'b', c]` into its scalars
+///
+/// A single scalar is read as a sequence of one.
+fn unquote_sequence(value: &str) -> Vec<String> {
+    let Some(inner) = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+    else {
+        return vec![unquote(value)];
+    };
+
+    // Split on commas outside of quotes
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut quote_char = None;
+    let mut escaped = false;
+    for (index, c) in inner.char_indices() {
+        match (quote_char, c) {
+            (Some('"'), _) if escaped => escaped = false,
+            (Some('"'), '\\') => escaped = true,
+            (Some(open), c) if c == open => quote_char = None,
+            (None, '"' | '\'') => quote_char = Some(c),
+            (None, ',') => {
+                items.push(&inner[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&inner[start..]);
+
+    items
+        .into_iter()
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Reads a plain, single-quoted, or double-quoted YAML scalar
+fn unquote(value: &str) -> String {
+    if let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        let mut unquoted = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unquoted.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some('r') => unquoted.push('\r'),
+                Some('t') => unquoted.push('\t'),
+                Some(other) => unquoted.push(other),
+                None => unquoted.push('\\'),
+            }
+        }
+        unquoted
+    } else if let Some(inner) = value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+    {
+        inner.replace("''", "'")
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,136 @@
+//! Conversion of markdown content to reStructuredText
+//!
+//! This is a basic line-based converter for the markdown found in typical
+//! specs: ATX headings, fenced code blocks, bullet lists, inline code, and
+//! inline links. Everything else is passed through unchanged, which is
+//! already valid RST for paragraphs, numbered lists, `*emphasis*`, and
+//! `**strong emphasis**`.
+
+/// Underline characters for heading levels 1 to 6
+const HEADING_UNDERLINES: [char; 6] = ['=', '-', '~', '^', '"', '\''];
+
+/// Converts markdown text to reStructuredText
+pub(crate) fn markdown_to_rst(markdown: &str) -> String {
+    let mut rst = String::with_capacity(markdown.len());
+    let mut lines = markdown.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(language) = code_fence(line) {
+            push_blank_line(&mut rst);
+            if language.is_empty() {
+                rst.push_str("::\n\n");
+            } else {
+                rst.push_str(&format!(".. code-block:: {}\n\n", language));
+            }
+            for code in lines.by_ref() {
+                if code_fence(code).is_some() {
+                    break;
+                }
+                if !code.is_empty() {
+                    rst.push_str("   ");
+                    rst.push_str(code);
+                }
+                rst.push('\n');
+            }
+            push_blank_line(&mut rst);
+        } else if let Some((level, title)) = heading(line) {
+            let title = convert_inline(title);
+            push_blank_line(&mut rst);
+            rst.push_str(&title);
+            rst.push('\n');
+            let underline = HEADING_UNDERLINES[level - 1];
+            rst.extend(std::iter::repeat_n(underline, title.chars().count()));
+            rst.push('\n');
+            if lines.peek().is_some_and(|next| !next.trim().is_empty()) {
+                rst.push('\n');
+            }
+        } else {
+            rst.push_str(&convert_inline(&convert_bullet(line)));
+            rst.push('\n');
+        }
+    }
+
+    rst
+}
+
+/// Returns the info string (e.g. the language) if `line` opens or closes a fenced code block
+fn code_fence(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("```")
+        .or_else(|| line.strip_prefix("~~~"))
+        .map(|info| info.trim_start_matches(['`', '~']).trim())
+}
+
+/// Returns the level and text of an ATX heading such as `## Design`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=HEADING_UNDERLINES.len()).contains(&level) {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?;
+    let title = title.trim().trim_end_matches('#').trim_end();
+    (!title.is_empty()).then_some((level, title))
+}
+
+/// Rewrites `*` and `+` bullet markers as `-`, keeping the indentation
+fn convert_bullet(line: &str) -> String {
+    let text = line.trim_start();
+    let indent = &line[..line.len() - text.len()];
+    match text.strip_prefix("* ").or_else(|| text.strip_prefix("+ ")) {
+        Some(item) => format!("{}- {}", indent, item),
+        None => line.to_string(),
+    }
+}
+
+/// Converts inline code and inline links to their RST syntax
+fn convert_inline(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['`', '[']) {
+        converted.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if let Some(code) = tail.strip_prefix('`') {
+            if let Some(end) = code.find('`').filter(|&end| end > 0) {
+                converted.push_str(&format!("``{}``", &code[..end]));
+                rest = &code[end + 1..];
+                continue;
+            }
+        } else if let Some((label, url, len)) = inline_link(tail) {
+            // Anonymous references (`__`) avoid clashes between links with equal labels
+            converted.push_str(&format!("`{} <{}>`__", label, url));
+            rest = &tail[len..];
+            continue;
+        }
+
+        // Both markers are ASCII, so this is a char boundary
+        converted.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+
+    converted.push_str(rest);
+    converted
+}
+
+/// Parses a `[label](url)` link at the start of `text`
+///
+/// Returns the label, the URL, and the number of bytes the link spans.
+fn inline_link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let label = &text[1..label_end];
+    if label.is_empty() || label.contains(['[', ']']) {
+        return None;
+    }
+    let url_start = label_end + 2;
+    let url_len = text[url_start..].find(')')?;
+    let url = &text[url_start..url_start + url_len];
+    Some((label, url, url_start + url_len + 1))
+}
+
+/// Makes sure `rst` ends with an empty line, unless nothing was written yet
+fn push_blank_line(rst: &mut String) {
+    if !rst.is_empty() && !rst.ends_with("\n\n") {
+        rst.push('\n');
+    }
+}
@@ -0,0 +1,69 @@
+//! Splitting markdown content into sections at its headings
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+/// A section of markdown content starting with a heading
+pub(crate) struct Section<'a> {
+    /// Plain text of the heading, without markup
+    pub(crate) title: String,
+    /// The section verbatim, from its heading up to the next heading of the same or a higher level
+    pub(crate) text: &'a str,
+}
+
+/// Returns the sections of `content` that start with a heading of `level`
+///
+/// Both ATX (`## Title`) and setext headings count. Headings inside block
+/// quotes, list items, and footnotes don't start a section, and neither does
+/// anything in code blocks. Text before the first heading of `level`, or
+/// under a heading of a higher level (a smaller number), belongs to no
+/// section.
+pub(crate) fn split_at_headings(content: &str, level: u32) -> Vec<Section<'_>> {
+    // Headings of `level` or higher: where they start, their level, and their text
+    let mut headings: Vec<(usize, u32, String)> = Vec::new();
+    let mut nesting = 0;
+    let mut heading_text = None;
+
+    for (event, range) in Parser::new(content).into_offset_iter() {
+        match event {
+            Event::Start(Tag::BlockQuote(_) | Tag::Item | Tag::FootnoteDefinition(_)) => {
+                nesting += 1
+            }
+            Event::End(TagEnd::BlockQuote(_) | TagEnd::Item | TagEnd::FootnoteDefinition) => {
+                nesting -= 1
+            }
+            Event::Start(Tag::Heading {
+                level: heading_level,
+                ..
+            }) if nesting == 0 && heading_level as u32 <= level => {
+                headings.push((range.start, heading_level as u32, String::new()));
+                heading_text = Some(String::new());
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(title) = heading_text.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let (Some(title), Some(heading)) = (heading_text.take(), headings.last_mut()) {
+                    heading.2 = title.trim().to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut sections = Vec::new();
+    for (index, (start, heading_level, title)) in headings.iter().enumerate() {
+        if *heading_level != level {
+            continue;
+        }
+        let end = headings
+            .get(index + 1)
+            .map_or(content.len(), |(next_start, _, _)| *next_start);
+        sections.push(Section {
+            title: title.clone(),
+            text: content[*start..end].trim_end(),
+        });
+    }
+    sections
+}
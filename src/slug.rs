@@ -0,0 +1,53 @@
+//! URL-friendly slugs derived from specfile names
+
+/// Slug used for names that contain no alphanumeric characters
+const FALLBACK_SLUG: &str = "spec";
+
+/// Converts a name into a lowercase, hyphen-separated slug
+///
+/// Alphanumeric characters are kept and lowercased, runs of whitespace,
+/// hyphens, and underscores become a single hyphen, and everything else is
+/// dropped. The result may be empty.
+pub(crate) fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Returns the base slug for a name, never empty
+pub(crate) fn base_slug(name: &str) -> String {
+    let slug = slugify(name);
+    if slug.is_empty() {
+        FALLBACK_SLUG.to_string()
+    } else {
+        slug
+    }
+}
+
+/// Returns true if `slug` is `base` or `base` with a numeric `-N` suffix
+pub(crate) fn has_base(slug: &str, base: &str) -> bool {
+    match slug.strip_prefix(base) {
+        Some("") => true,
+        Some(suffix) => suffix
+            .strip_prefix('-')
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// Picks `base`, or the first of `base-2`, `base-3`, ... that is not taken
+pub(crate) fn first_free(base: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|suffix| format!("{}-{}", base, suffix))
+        .find(|candidate| !is_taken(candidate))
+        .expect("an unused suffix always exists")
+}
@@ -1,19 +1,29 @@
-use lib_specbase::{SpecBase, Specfile};
-use std::{env, fs};
+use lib_specbase::{
+    CheckpointMode, QueryMode, QueryParams, SortDirection, SortKey, SpecBase, SpecError, Specfile,
+    SpecfilePatch, TimelineEvent, CURRENT_FORMAT_VERSION, SCHEMA_VERSION,
+};
+use std::fs;
+use std::time::{Duration, Instant};
 use tempfile::tempdir;
 
+/// Returns a new specfile with the given fields and defaults for the rest
+fn spec(name: &str, content: &str, tags: &[&str]) -> Specfile {
+    Specfile {
+        id: None,
+        name: name.to_string(),
+        description: String::new(),
+        content: content.to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
 #[test]
 fn test_specbase_crud_operations() {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir().unwrap();
-    let config_path = temp_dir.path().join(".config").join("specbase");
-    fs::create_dir_all(&config_path).unwrap();
-
-    // Set the HOME environment variable to our temp directory
-    env::set_var("HOME", temp_dir.path());
-
-    // Initialize database
-    let spec_db = SpecBase::init().unwrap();
+    // Initialize an in-memory database
+    let spec_db = SpecBase::init_in_memory().unwrap();
 
     // Test create
     let test_spec = Specfile {
@@ -21,10 +31,15 @@ fn test_specbase_crud_operations() {
         name: "Test Spec".to_string(),
         description: "Test Description".to_string(),
         content: "Test Content".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
     };
 
     let id = spec_db.create_specfile(&test_spec).unwrap();
     assert!(id > 0);
+    assert!(spec_db.specfile_exists(id).unwrap());
 
     // Test read
     let retrieved_spec = spec_db.read_specfile(id).unwrap();
@@ -38,6 +53,10 @@ fn test_specbase_crud_operations() {
         name: "Updated Name".to_string(),
         description: "Updated Description".to_string(),
         content: "Updated Content".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
     };
 
     spec_db.update_specfile(id, &updated_spec).unwrap();
@@ -56,14 +75,2099 @@ fn test_specbase_crud_operations() {
     // Test delete
     spec_db.delete_specfile(id).unwrap();
     assert!(spec_db.read_specfile(id).is_err());
+
+    // Test close
+    spec_db.close().unwrap();
 }
 
 #[test]
-fn test_specfile_not_found() {
+fn test_init_at_creates_parent_directories() {
     let temp_dir = tempdir().unwrap();
-    env::set_var("HOME", temp_dir.path());
+    let db_path = temp_dir
+        .path()
+        .join("project")
+        .join("specs")
+        .join("specbase.db");
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    assert_eq!(spec_db.list_specfiles().unwrap().len(), 0);
+    assert!(db_path.is_file());
+
+    // A file in place of the parent directory can't be turned into one
+    let blocker = temp_dir.path().join("blocker");
+    fs::write(&blocker, "").unwrap();
+    let Err(error) = SpecBase::init_at(blocker.join("specbase.db")) else {
+        panic!("opening a database below a file should fail");
+    };
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::DatabaseDirError { .. })
+    ));
+}
 
-    let spec_db = SpecBase::init().unwrap();
+#[test]
+fn test_specfile_not_found() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
     assert!(spec_db.read_specfile(999).is_err());
+    assert!(spec_db.get_specfile(999).unwrap().is_none());
+    assert!(!spec_db.specfile_exists(999).unwrap());
     assert!(spec_db.delete_specfile(999).is_err());
 }
+
+#[test]
+fn test_read_specfile_reports_database_errors() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let error = spec_db.read_specfile(999).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::SpecfileNotFound(999))
+    ));
+
+    // A broken schema is a database error, not a missing specfile
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("broken.db");
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch("DROP TABLE specfiles").unwrap();
+    drop(conn);
+    let error = spec_db.read_specfile(1).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::DatabaseError(_))
+    ));
+}
+
+#[test]
+fn test_create_specfiles() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let specs: Vec<Specfile> = (0..100)
+        .map(|i| spec(&format!("Spec {}", i), &format!("Content {}", i), &[]))
+        .collect();
+
+    // IDs are returned in the order of the input
+    let ids = spec_db.create_specfiles(&specs).unwrap();
+    assert_eq!(ids.len(), 100);
+    for (i, id) in ids.iter().enumerate() {
+        assert_eq!(
+            spec_db.read_specfile(*id).unwrap().name,
+            format!("Spec {}", i)
+        );
+    }
+
+    // One invalid specfile fails the whole batch
+    let spec_db = SpecBase::builder()
+        .name_pattern("[a-z-]+")
+        .init_in_memory()
+        .unwrap();
+    let batch = [spec("valid-name", "", &[]), spec("Invalid Name", "", &[])];
+    assert!(spec_db.create_specfiles(&batch).is_err());
+    assert!(spec_db.list_specfiles().unwrap().is_empty());
+
+    // A failing insert rolls back the ones before it
+    let spec_db = SpecBase::builder()
+        .unique_names(true)
+        .init_in_memory()
+        .unwrap();
+    let batch = [
+        spec("First", "", &[]),
+        spec("Twice", "", &[]),
+        spec("Twice", "", &[]),
+    ];
+    assert!(spec_db.create_specfiles(&batch).is_err());
+    assert_eq!(spec_db.count_specfiles().unwrap(), 0);
+}
+
+/// Compares batched and looped inserts on disk; run with `cargo test -- --ignored`
+#[test]
+#[ignore]
+fn bench_create_specfiles() {
+    let temp_dir = tempdir().unwrap();
+    let specs: Vec<Specfile> = (0..1000)
+        .map(|i| spec(&format!("Spec {}", i), &format!("Content {}", i), &[]))
+        .collect();
+
+    // On a file-backed database every single insert commits to disk
+    let looped_db = SpecBase::init_at(temp_dir.path().join("looped.db")).unwrap();
+    let start = Instant::now();
+    for spec in &specs {
+        looped_db.create_specfile(spec).unwrap();
+    }
+    let looped = start.elapsed();
+
+    let batch_db = SpecBase::init_at(temp_dir.path().join("batch.db")).unwrap();
+    let start = Instant::now();
+    batch_db.create_specfiles(&specs).unwrap();
+    let batched = start.elapsed();
+    println!("1000 inserts: looped {:?}, batched {:?}", looped, batched);
+    assert!(batched < looped);
+}
+
+#[test]
+fn test_autoincrement() {
+    // Purging removes the row, so only AUTOINCREMENT keeps its ID from coming back
+    for (enabled, reused) in [(false, true), (true, false)] {
+        let spec_db = SpecBase::builder()
+            .autoincrement(enabled)
+            .init_in_memory()
+            .unwrap();
+        spec_db.create_specfile(&spec("First", "", &[])).unwrap();
+        let last = spec_db.create_specfile(&spec("Last", "", &[])).unwrap();
+        spec_db.delete_specfile(last).unwrap();
+        spec_db.purge_deleted().unwrap();
+
+        let next = spec_db.create_specfile(&spec("Next", "", &[])).unwrap();
+        assert_eq!(next == last, reused, "autoincrement({enabled})");
+    }
+}
+
+#[test]
+fn test_clone_specfile() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let source = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Login".to_string(),
+            description: "Sign in".to_string(),
+            content: "# Login".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: vec!["auth".to_string()],
+        })
+        .unwrap();
+
+    let copy = spec_db.clone_specfile(source).unwrap();
+    assert_ne!(copy, source);
+    let original = spec_db.read_specfile(source).unwrap();
+    let cloned = spec_db.read_specfile(copy).unwrap();
+    assert_eq!(cloned.name, "Copy of Login");
+    assert_eq!(cloned.description, original.description);
+    assert_eq!(cloned.content, original.content);
+    assert_eq!(cloned.tags, original.tags);
+    assert_ne!(spec_db.slug(copy).unwrap(), spec_db.slug(source).unwrap());
+
+    let error = spec_db.clone_specfile(999).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::SpecfileNotFound(999))
+    ));
+}
+
+#[test]
+fn test_split_by_heading() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let content = "# Accounts\nIntro\n\n## Login\nSign in.\n\n```\n## Not a heading\n```\n\n\
+                   > ## Quoted\n\n## `Logout` flow\nBye.\n\n# Appendix\nNotes";
+    let source = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Accounts".to_string(),
+            description: "Account handling".to_string(),
+            content: content.to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: vec!["auth".to_string()],
+        })
+        .unwrap();
+
+    let ids = spec_db.split_by_heading(source, 2, false).unwrap();
+    assert_eq!(ids.len(), 2);
+    let login = spec_db.read_specfile(ids[0]).unwrap();
+    assert_eq!(login.name, "Login");
+    assert_eq!(
+        login.content,
+        format!(
+            "## Login\nSign in.\n\n```\n## Not a heading\n```\n\n> ## Quoted\n\n\
+             Split from spec://{source}"
+        )
+    );
+    assert_eq!(login.description, "Account handling");
+    assert_eq!(login.tags, ["auth"]);
+    let logout = spec_db.read_specfile(ids[1]).unwrap();
+    assert_eq!(logout.name, "Logout flow");
+    assert!(logout
+        .content
+        .starts_with("## `Logout` flow\nBye.\n\nSplit from"));
+    assert_eq!(spec_db.read_specfile(source).unwrap().content, content);
+
+    assert!(spec_db
+        .split_by_heading(source, 3, true)
+        .unwrap()
+        .is_empty());
+    assert!(spec_db.specfile_exists(source).unwrap());
+    let error = spec_db.split_by_heading(source, 7, false).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::InvalidHeadingLevel(7))
+    ));
+
+    // Deleting the original trashes it but keeps the back-references
+    let ids = spec_db.split_by_heading(source, 1, true).unwrap();
+    assert_eq!(ids.len(), 2);
+    assert!(!spec_db.specfile_exists(source).unwrap());
+    assert_eq!(spec_db.list_deleted().unwrap()[0].0.id, Some(source));
+    assert_eq!(
+        spec_db.read_specfile(ids[1]).unwrap().content,
+        format!("# Appendix\nNotes\n\nSplit from spec://{source}")
+    );
+    assert!(spec_db.validate_all_references().unwrap().is_empty());
+    let trashed = spec_db.find_trashed_references().unwrap();
+    assert!(ids.iter().all(|id| trashed.contains(&(*id, vec![source]))));
+}
+
+#[test]
+fn test_update_specfile_fields() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Partial".to_string(),
+            description: "Draft".to_string(),
+            content: "Body".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: vec!["api".to_string()],
+        })
+        .unwrap();
+
+    spec_db
+        .update_specfile_fields(id, None, Some("Reviewed"), None)
+        .unwrap();
+    let specfile = spec_db.read_specfile(id).unwrap();
+    assert_eq!(specfile.name, "Partial");
+    assert_eq!(specfile.description, "Reviewed");
+    assert_eq!(specfile.content, "Body");
+    assert_eq!(specfile.tags, ["api"]);
+
+    spec_db
+        .update_specfile_fields(id, Some("Partial Update"), None, Some("New body"))
+        .unwrap();
+    let specfile = spec_db.read_specfile(id).unwrap();
+    assert_eq!(specfile.name, "Partial Update");
+    assert_eq!(specfile.description, "Reviewed");
+    assert_eq!(specfile.content, "New body");
+    assert_eq!(spec_db.slug(id).unwrap().unwrap(), "partial-update");
+
+    // Nothing to change is a no-op, even for a missing specfile
+    spec_db
+        .update_specfile_fields(id + 1, None, None, None)
+        .unwrap();
+    let error = spec_db
+        .update_specfile_fields(id + 1, None, Some("Reviewed"), None)
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::SpecfileNotFound(_))
+    ));
+}
+
+#[test]
+fn test_patch_many() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let mut ids = Vec::new();
+    for name in ["First", "Second", "Third"] {
+        let spec = Specfile {
+            id: None,
+            name: name.to_string(),
+            description: "Draft".to_string(),
+            content: format!("Content of {}", name),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        };
+        ids.push(spec_db.create_specfile(&spec).unwrap());
+    }
+
+    let patch = SpecfilePatch {
+        description: Some("Reviewed".to_string()),
+        ..Default::default()
+    };
+
+    // Unknown IDs are skipped and not counted
+    let updated = spec_db.patch_many(&[ids[0], ids[2], 999], patch).unwrap();
+    assert_eq!(updated, 2);
+
+    let first = spec_db.read_specfile(ids[0]).unwrap();
+    assert_eq!(first.description, "Reviewed");
+    assert_eq!(first.name, "First");
+    assert_eq!(first.content, "Content of First");
+    assert_eq!(spec_db.read_specfile(ids[1]).unwrap().description, "Draft");
+    assert_eq!(
+        spec_db.read_specfile(ids[2]).unwrap().description,
+        "Reviewed"
+    );
+
+    // An empty patch is a no-op
+    assert_eq!(
+        spec_db.patch_many(&ids, SpecfilePatch::default()).unwrap(),
+        0
+    );
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_round_trip() {
+    let temp_dir = tempdir().unwrap();
+    let spec_db = SpecBase::init_at(temp_dir.path().join("specbase.db")).unwrap();
+    let spec = Specfile {
+        id: None,
+        name: "Zip \"Spec\"".to_string(),
+        description: "Line one\nline: two".to_string(),
+        content: "# Heading\n\n---\nBody".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: vec!["a, \"b\"".to_string(), "c".to_string()],
+    };
+    let id = spec_db.create_specfile(&spec).unwrap();
+
+    let archive = temp_dir.path().join("specs.zip");
+    assert_eq!(spec_db.export_zip(&archive).unwrap(), 1);
+
+    let imported = spec_db.import_zip(&archive).unwrap();
+    assert_eq!(imported.len(), 1);
+    assert_ne!(imported[0], id);
+
+    let copy = spec_db.read_specfile(imported[0]).unwrap();
+    assert_eq!(copy.name, spec.name);
+    assert_eq!(copy.description, spec.description);
+    assert_eq!(copy.content, spec.content);
+    assert_eq!(copy.tags, spec.tags);
+}
+
+#[test]
+fn test_dir_round_trip() {
+    let temp_dir = tempdir().unwrap();
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Rate Limits".to_string(),
+            description: "Per-user quotas".to_string(),
+            content: "# Rate Limits\n\nBody".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: vec!["api".to_string()],
+        })
+        .unwrap();
+
+    let dir = temp_dir.path().join("nested").join("specs");
+    assert_eq!(spec_db.export_to_dir(&dir).unwrap(), 1);
+
+    let text = fs::read_to_string(dir.join(format!("{}-rate-limits.md", id))).unwrap();
+    assert!(text.starts_with(&format!("---\nid: {}\nname: \"Rate Limits\"\n", id)));
+    assert!(text.contains("description: \"Per-user quotas\"\n"));
+    assert!(text.ends_with("---\n# Rate Limits\n\nBody"));
+
+    // Files without frontmatter are named after their first heading or file name
+    fs::write(dir.join("notes.md"), "Plain text").unwrap();
+    fs::write(dir.join("ignored.txt"), "Not markdown").unwrap();
+
+    let imported = spec_db.import_from_dir(&dir).unwrap();
+    assert_eq!(imported.len(), 2);
+    let copy = spec_db.read_specfile(imported[0]).unwrap();
+    assert_ne!(copy.id, Some(id));
+    assert_eq!(copy.name, "Rate Limits");
+    assert_eq!(copy.description, "Per-user quotas");
+    assert_eq!(copy.content, "# Rate Limits\n\nBody");
+    assert_eq!(copy.tags, ["api"]);
+
+    let notes = spec_db.read_specfile(imported[1]).unwrap();
+    assert_eq!(notes.name, "notes");
+    assert_eq!(notes.description, "");
+    assert_eq!(notes.content, "Plain text");
+}
+
+#[test]
+fn test_find_broken_links_reports_malformed_urls() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let with_links = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Links".to_string(),
+            description: "Has broken links".to_string(),
+            content: "See [bad](https://-bad-.example) and <http://host:99999/x>.".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+    let without_links = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Plain".to_string(),
+            description: "No links".to_string(),
+            content: "Nothing to see here".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+
+    let broken = spec_db.find_broken_links().unwrap();
+    let urls = &broken.iter().find(|(id, _)| *id == with_links).unwrap().1;
+    assert_eq!(urls, &["https://-bad-.example", "http://host:99999/x"]);
+    assert!(broken.iter().all(|(id, _)| *id != without_links));
+}
+
+#[test]
+fn test_validate_all_references() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let target = spec_db
+        .create_specfile(&spec("Refs", "Target", &[]))
+        .unwrap();
+    let source = spec_db
+        .create_specfile(&spec(
+            "Refs",
+            &format!("See spec://{target}, spec://999 and [old](spec://998). Again: spec://999"),
+            &[],
+        ))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("Refs", "spec:// alone", &[]))
+        .unwrap();
+
+    assert_eq!(
+        spec_db.validate_all_references().unwrap(),
+        vec![(source, vec![999, 998])]
+    );
+
+    assert!(spec_db.find_trashed_references().unwrap().is_empty());
+
+    spec_db.delete_specfile(target).unwrap();
+    assert_eq!(
+        spec_db.validate_all_references().unwrap(),
+        vec![(source, vec![999, 998])]
+    );
+    assert_eq!(
+        spec_db.find_trashed_references().unwrap(),
+        vec![(source, vec![target])]
+    );
+
+    spec_db.purge_deleted().unwrap();
+    assert_eq!(
+        spec_db.validate_all_references().unwrap(),
+        vec![(source, vec![target, 999, 998])]
+    );
+    assert!(spec_db.find_trashed_references().unwrap().is_empty());
+}
+
+#[test]
+fn test_find_cycles() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let first = spec_db.create_specfile(&spec("Refs", "", &[])).unwrap();
+    let second = spec_db
+        .create_specfile(&spec(
+            "Refs",
+            &format!("spec://{first} and spec://999"),
+            &[],
+        ))
+        .unwrap();
+    assert!(spec_db.find_cycles().unwrap().is_empty());
+
+    let third = spec_db
+        .create_specfile(&spec("Refs", &format!("spec://{second}"), &[]))
+        .unwrap();
+    spec_db
+        .update_specfile(
+            first,
+            &spec("Refs", &format!("spec://{third}, spec://{third}"), &[]),
+        )
+        .unwrap();
+    let own = spec_db
+        .create_specfile(&spec("Refs", "Points at itself", &[]))
+        .unwrap();
+    spec_db
+        .update_specfile(own, &spec("Refs", &format!("See spec://{own}"), &[]))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("Refs", &format!("spec://{first}"), &[]))
+        .unwrap();
+
+    assert_eq!(
+        spec_db.find_cycles().unwrap(),
+        vec![vec![first, third, second], vec![own]]
+    );
+}
+
+#[test]
+fn test_find_stubs() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let empty = spec_db.create_specfile(&spec("Stub", "", &[])).unwrap();
+    let blank = spec_db
+        .create_specfile(&spec("Stub", "  \n\t\n", &[]))
+        .unwrap();
+    let short = spec_db
+        .create_specfile(&spec("Stub", "\n# TODO\n\n", &[]))
+        .unwrap();
+    let filled = spec_db
+        .create_specfile(&spec("Stub", "# Done\n\nBody", &[]))
+        .unwrap();
+
+    let ids = |min_length| -> Vec<Option<i64>> {
+        let stubs = spec_db.find_stubs(min_length).unwrap();
+        stubs.into_iter().map(|specfile| specfile.id).collect()
+    };
+    assert_eq!(ids(0), []);
+    assert_eq!(ids(1), [Some(empty), Some(blank)]);
+    assert_eq!(ids(7), [Some(empty), Some(blank), Some(short)]);
+    assert_eq!(ids(12), [Some(empty), Some(blank), Some(short)]);
+    assert_eq!(
+        ids(13),
+        [Some(empty), Some(blank), Some(short), Some(filled)]
+    );
+}
+
+#[test]
+fn test_dump_sql_restores_into_empty_database() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let spec = Specfile {
+        id: None,
+        name: "It's \"quoted\"".to_string(),
+        description: "Semicolons; and 'quotes'".to_string(),
+        content: "Line one\nLine two -- not a comment".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
+    };
+    let id = spec_db.create_specfile(&spec).unwrap();
+
+    let mut dump = Vec::new();
+    spec_db.dump_sql(&mut dump).unwrap();
+
+    let restored = rusqlite::Connection::open_in_memory().unwrap();
+    restored
+        .execute_batch(&String::from_utf8(dump).unwrap())
+        .unwrap();
+    let (name, description, content): (String, String, String) = restored
+        .query_row(
+            "SELECT name, description, content FROM specfiles WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!(name, spec.name);
+    assert_eq!(description, spec.description);
+    assert_eq!(content, spec.content);
+
+    // The full-text index is rebuilt and the schema isn't migrated again
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("restored.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let mut dump = Vec::new();
+    spec_db.dump_sql(&mut dump).unwrap();
+    conn.execute_batch(&String::from_utf8(dump).unwrap())
+        .unwrap();
+    drop(conn);
+    let restored_db = SpecBase::init_at(&db_path).unwrap();
+    assert_eq!(restored_db.search_specfiles("semicolons").unwrap().len(), 1);
+}
+
+#[test]
+fn test_slugs_are_unique_and_stable() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let spec = Specfile {
+        id: None,
+        name: "Rate Limits (v2)".to_string(),
+        description: "Throttling".to_string(),
+        content: "Requests per second".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
+    };
+    let first = spec_db.create_specfile(&spec).unwrap();
+    let second = spec_db.create_specfile(&spec).unwrap();
+
+    assert_eq!(spec_db.slug(first).unwrap().unwrap(), "rate-limits-v2");
+    assert_eq!(spec_db.slug(second).unwrap().unwrap(), "rate-limits-v2-2");
+    assert_eq!(
+        spec_db.read_by_slug("rate-limits-v2-2").unwrap().id,
+        Some(second)
+    );
+    assert!(spec_db.read_by_slug("missing").is_err());
+
+    // Updating without renaming keeps the slug, renaming replaces it
+    spec_db.update_specfile(second, &spec).unwrap();
+    assert_eq!(spec_db.slug(second).unwrap().unwrap(), "rate-limits-v2-2");
+    let renamed = Specfile {
+        name: "Quotas".to_string(),
+        ..spec
+    };
+    spec_db.update_specfile(second, &renamed).unwrap();
+    assert_eq!(spec_db.slug(second).unwrap().unwrap(), "quotas");
+}
+
+#[test]
+fn test_word_and_char_count() {
+    let spec = Specfile::scaffold("Größe", "", "# {name}\n\n  Two\twords\u{3000}three \n");
+    assert_eq!(spec.word_count(), 5);
+    assert_eq!(spec.char_count(), 28);
+
+    let empty = Specfile::scaffold("Empty", "", " \n\t");
+    assert_eq!(empty.word_count(), 0);
+    assert_eq!(empty.char_count(), 3);
+}
+
+#[test]
+fn test_update_specfile_checked_detects_conflicts() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Shared".to_string(),
+            description: "Edited twice".to_string(),
+            content: "Original".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+
+    // Two editors read the same version
+    let first_read = spec_db.read_specfile(id).unwrap();
+    let second_read = spec_db.read_specfile(id).unwrap();
+
+    let first_edit = Specfile {
+        content: "First edit".to_string(),
+        ..first_read.clone()
+    };
+    spec_db
+        .update_specfile_checked(id, &first_edit, first_read.version())
+        .unwrap();
+
+    // The second write is based on a stale version and must not clobber the first
+    let second_edit = Specfile {
+        content: "Second edit".to_string(),
+        ..second_read.clone()
+    };
+    let error = spec_db
+        .update_specfile_checked(id, &second_edit, second_read.version())
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::ConflictDetected { .. })
+    ));
+    assert_eq!(spec_db.read_specfile(id).unwrap().content, "First edit");
+}
+
+#[test]
+fn test_returning_variants() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let original = Specfile {
+        id: None,
+        name: "Undoable".to_string(),
+        description: "Before".to_string(),
+        content: "Original".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: vec!["draft".to_string()],
+    };
+    let id = spec_db.create_specfile(&original).unwrap();
+
+    let edited = Specfile {
+        description: "After".to_string(),
+        ..original.clone()
+    };
+    let previous = spec_db.update_specfile_returning(id, &edited).unwrap();
+    assert_eq!(previous.id, Some(id));
+    assert_eq!(previous.description, "Before");
+    assert_eq!(previous.tags, ["draft"]);
+    assert_eq!(spec_db.read_specfile(id).unwrap().description, "After");
+
+    let deleted = spec_db.delete_specfile_returning(id).unwrap();
+    assert_eq!(deleted.description, "After");
+    assert!(spec_db.get_specfile(id).unwrap().is_none());
+
+    for error in [
+        spec_db.update_specfile_returning(id, &edited).unwrap_err(),
+        spec_db.delete_specfile_returning(id).unwrap_err(),
+    ] {
+        assert!(matches!(
+            error.downcast_ref::<SpecError>(),
+            Some(SpecError::SpecfileNotFound(_))
+        ));
+    }
+}
+
+#[test]
+fn test_list_outdated_format() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let mut ids = Vec::new();
+    for format_version in [1, 2, 1] {
+        let id = spec_db
+            .create_specfile(&Specfile {
+                id: None,
+                name: format!("Format {}", format_version),
+                description: String::new(),
+                content: String::new(),
+                format_version,
+                created_at: None,
+                updated_at: None,
+                tags: Vec::new(),
+            })
+            .unwrap();
+        ids.push(id);
+    }
+
+    let outdated: Vec<i64> = spec_db
+        .list_outdated_format(2)
+        .unwrap()
+        .iter()
+        .map(|spec| spec.id.unwrap())
+        .collect();
+    assert_eq!(outdated, vec![ids[0], ids[2]]);
+    assert_eq!(spec_db.read_specfile(ids[1]).unwrap().format_version, 2);
+    assert!(spec_db
+        .list_outdated_format(CURRENT_FORMAT_VERSION)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_query_modes() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let mut ids = Vec::new();
+    for content in ["rate limit per user", "limit the rate", "rate only"] {
+        let id = spec_db
+            .create_specfile(&Specfile {
+                id: None,
+                name: "Spec".to_string(),
+                description: String::new(),
+                content: content.to_string(),
+                format_version: CURRENT_FORMAT_VERSION,
+                created_at: None,
+                updated_at: None,
+                tags: Vec::new(),
+            })
+            .unwrap();
+        ids.push(id);
+    }
+
+    let phrase = spec_db
+        .query_ids_with_mode("rate limit", QueryMode::Phrase)
+        .unwrap();
+    assert_eq!(phrase, vec![ids[0]]);
+    assert_eq!(phrase, spec_db.query_ids("rate limit").unwrap());
+
+    let all = spec_db
+        .query_ids_with_mode("rate limit", QueryMode::All)
+        .unwrap();
+    assert_eq!(all, vec![ids[0], ids[1]]);
+
+    let any = spec_db
+        .query_ids_with_mode("rate  limit", QueryMode::Any)
+        .unwrap();
+    assert_eq!(any, ids);
+
+    assert_eq!(spec_db.count_specfiles().unwrap(), 3);
+    let pages: Vec<i64> = [0, 2, 4]
+        .into_iter()
+        .flat_map(|offset| spec_db.list_specfiles_paged(2, offset).unwrap())
+        .map(|spec| spec.id.unwrap())
+        .collect();
+    assert_eq!(pages, ids);
+    assert_eq!(spec_db.count_query("rate limit").unwrap(), 1);
+    assert_eq!(spec_db.count_query("rate").unwrap(), 3);
+}
+
+#[cfg(feature = "rst")]
+#[test]
+fn test_read_specfile_rst() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Rst".to_string(),
+            description: String::new(),
+            content: "# Rate Limits\nUse `limit` as in [RFC](https://example.com).\n\n## Design\n\n* one\n\n```rust\nlet x = 1;\n```"
+                .to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+
+    assert_eq!(
+        spec_db.read_specfile_rst(id).unwrap(),
+        "Rate Limits\n===========\n\nUse ``limit`` as in `RFC <https://example.com>`__.\n\n\
+         Design\n------\n\n- one\n\n.. code-block:: rust\n\n   let x = 1;\n\n"
+    );
+    assert!(spec_db.read_specfile_rst(id + 1).is_err());
+}
+
+#[cfg(feature = "confluence")]
+#[test]
+fn test_read_specfile_confluence() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Confluence".to_string(),
+            description: String::new(),
+            content: "# Limits & Quotas\nUse `a<b` as in [RFC](https://example.com/?a=1&b=2).\n\n\
+                      * one\n* **two**\n\n1. first\n\n| Key | Value |\n|---|:-:|\n| a | b |\n\n\
+                      ```rust\nlet x = \"]]>\";\n```"
+                .to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+
+    assert_eq!(
+        spec_db.read_specfile_confluence(id).unwrap(),
+        "<h1>Limits &amp; Quotas</h1>\n\
+         <p>Use <code>a&lt;b</code> as in <a href=\"https://example.com/?a=1&amp;b=2\">RFC</a>.</p>\n\
+         <ul>\n<li>one</li>\n<li><strong>two</strong></li>\n</ul>\n\
+         <ol>\n<li>first</li>\n</ol>\n\
+         <table><tbody>\n<tr><th>Key</th><th>Value</th></tr>\n<tr><td>a</td><td>b</td></tr>\n</tbody></table>\n\
+         <ac:structured-macro ac:name=\"code\"><ac:parameter ac:name=\"language\">rust</ac:parameter>\
+         <ac:plain-text-body><![CDATA[let x = \"]]]]><![CDATA[>\";\n]]></ac:plain-text-body>\
+         </ac:structured-macro>\n"
+    );
+    assert!(spec_db.read_specfile_confluence(id + 1).is_err());
+}
+
+#[test]
+fn test_name_pattern_rejects_nonconforming_names() {
+    let temp_dir = tempdir().unwrap();
+    let spec_db = SpecBase::builder()
+        .path(temp_dir.path().join("specbase.db"))
+        .name_pattern("[A-Z]+-[0-9]{3}-.+")
+        .init()
+        .unwrap();
+    let spec = Specfile {
+        id: None,
+        name: "AUTH-001-login".to_string(),
+        description: String::new(),
+        content: String::new(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
+    };
+    let id = spec_db.create_specfile(&spec).unwrap();
+
+    // The whole name must match, not just a part of it
+    let renamed = Specfile {
+        name: "see AUTH-001-login".to_string(),
+        ..spec
+    };
+    let error = spec_db.update_specfile(id, &renamed).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::NameConventionViolation { name, .. }) if name == "see AUTH-001-login"
+    ));
+    assert!(spec_db.create_specfile(&renamed).is_err());
+    assert_eq!(spec_db.read_specfile(id).unwrap().name, "AUTH-001-login");
+
+    let invalid = SpecBase::builder()
+        .path(temp_dir.path().join("specbase.db"))
+        .name_pattern("(")
+        .init();
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn test_unique_names() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("specbase.db");
+    let is_duplicate = |result: anyhow::Result<i64>, name: &str| {
+        matches!(
+            result.unwrap_err().downcast_ref::<SpecError>(),
+            Some(SpecError::DuplicateName(duplicate)) if duplicate == name
+        )
+    };
+
+    // Duplicates are allowed by default, but prevent enforcing unique names
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    let login = spec_db.create_specfile(&spec("Login", "", &[])).unwrap();
+    let other = spec_db.create_specfile(&spec("Login", "", &[])).unwrap();
+    drop(spec_db);
+    let error = SpecBase::builder()
+        .path(&db_path)
+        .unique_names(true)
+        .init()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::DuplicateNamesExist(names)) if names == &["Login"]
+    ));
+
+    // Names in the trash don't count
+    SpecBase::init_at(&db_path)
+        .unwrap()
+        .delete_specfile(other)
+        .unwrap();
+    let spec_db = SpecBase::builder()
+        .path(&db_path)
+        .unique_names(true)
+        .init()
+        .unwrap();
+    assert!(is_duplicate(
+        spec_db.create_specfile(&spec("Login", "", &[])),
+        "Login"
+    ));
+    let logout = spec_db.create_specfile(&spec("Logout", "", &[])).unwrap();
+    assert!(is_duplicate(
+        spec_db
+            .update_specfile(logout, &spec("Login", "", &[]))
+            .map(|_| logout),
+        "Login"
+    ));
+    assert!(is_duplicate(
+        spec_db.restore_specfile(other).map(|_| other),
+        "Login"
+    ));
+    spec_db
+        .update_specfile(login, &spec("Sign in", "", &[]))
+        .unwrap();
+    spec_db.restore_specfile(other).unwrap();
+    drop(spec_db);
+
+    // The index stays in place without the option
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    assert!(is_duplicate(
+        spec_db.create_specfile(&spec("Logout", "", &[])),
+        "Logout"
+    ));
+}
+
+#[test]
+fn test_timestamps_are_set_and_backfilled() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Timed".to_string(),
+            description: String::new(),
+            content: String::new(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+    let created = spec_db.read_specfile(id).unwrap();
+    let created_at = created.created_at.clone().unwrap();
+    assert_eq!(created_at.len(), "2024-01-01T00:00:00Z".len());
+    assert!(created_at.ends_with('Z'));
+    assert_eq!(created.updated_at.as_ref(), Some(&created_at));
+
+    spec_db.update_specfile(id, &created).unwrap();
+    let updated = spec_db.read_specfile(id).unwrap();
+    assert_eq!(updated.created_at, Some(created_at.clone()));
+    assert!(updated.updated_at.unwrap() >= created_at);
+
+    // Databases created before timestamps existed are upgraded on open
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("old.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE specfiles (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            content TEXT NOT NULL
+        );
+        INSERT INTO specfiles (name, description, content) VALUES ('Old', '', '');",
+    )
+    .unwrap();
+    drop(conn);
+
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    let old = spec_db.list_specfiles().unwrap().remove(0);
+    assert!(old.created_at.is_some());
+    assert_eq!(old.created_at, old.updated_at);
+}
+
+#[test]
+fn test_schema_version() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    assert_eq!(spec_db.schema_version().unwrap(), SCHEMA_VERSION);
+
+    // Databases written by a newer version of the library are refused and left alone
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("future.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+        .unwrap();
+    drop(conn);
+    let error = SpecBase::init_at(&db_path).err().unwrap();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::UnsupportedSchemaVersion { found, supported })
+            if *found == SCHEMA_VERSION + 1 && *supported == SCHEMA_VERSION
+    ));
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let tables: i64 = conn
+        .query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(tables, 0);
+}
+
+#[test]
+fn test_connection() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let specs: Vec<Specfile> = ["Kept", "Trashed"]
+        .iter()
+        .map(|name| Specfile {
+            id: None,
+            name: name.to_string(),
+            description: String::new(),
+            content: "Custom SQL".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: vec!["sql".to_string()],
+        })
+        .collect();
+    let ids = spec_db.create_specfiles(&specs).unwrap();
+    spec_db.delete_specfile(ids[1]).unwrap();
+
+    let names: Vec<String> = spec_db
+        .connection()
+        .prepare(
+            "SELECT specfiles.name FROM specfiles
+             JOIN specfile_tags ON specfile_tags.specfile_id = specfiles.id
+             JOIN tags ON tags.id = specfile_tags.tag_id
+             WHERE tags.name = 'sql' AND specfiles.deleted_at IS NULL",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, ["Kept"]);
+
+    // Writes through the connection keep the full-text index in sync
+    spec_db
+        .connection()
+        .execute(
+            "UPDATE specfiles SET content = 'Rewritten' WHERE id = ?1",
+            [ids[0]],
+        )
+        .unwrap();
+    let found = spec_db.search_specfiles("rewritten").unwrap();
+    assert_eq!(found[0].id, Some(ids[0]));
+}
+
+#[test]
+fn test_open_existing() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("nested").join("specbase.db");
+
+    // Nothing is created for a missing database
+    let error = SpecBase::open_existing_at(&db_path).err().unwrap();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::DatabaseNotFound(path)) if *path == db_path
+    ));
+    assert!(!db_path.parent().unwrap().exists());
+
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Existing".to_string(),
+            description: String::new(),
+            content: "Content".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+    spec_db.close().unwrap();
+
+    let spec_db = SpecBase::builder().path(&db_path).open_existing().unwrap();
+    assert_eq!(spec_db.read_specfile(id).unwrap().name, "Existing");
+}
+
+#[test]
+fn test_init_immutable() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("specs #1.db");
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Frozen".to_string(),
+            description: String::new(),
+            content: "Read only".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: vec!["archive".to_string()],
+        })
+        .unwrap();
+    // Enough rows that a search runs into the progress handler
+    let filler: Vec<Specfile> = (0..200)
+        .map(|i| spec(&format!("Filler {i}"), "Padding", &[]))
+        .collect();
+    spec_db.create_specfiles(&filler).unwrap();
+    spec_db.close().unwrap();
+
+    let spec_db = SpecBase::init_immutable(&db_path).unwrap();
+    let specfile = spec_db.read_specfile(id).unwrap();
+    assert_eq!(specfile.name, "Frozen");
+    assert_eq!(spec_db.query_ids("read").unwrap(), vec![id]);
+    assert_eq!(spec_db.list_specfiles_by_tag("archive").unwrap().len(), 1);
+
+    let is_read_only = |error: anyhow::Error| {
+        matches!(error.downcast_ref::<SpecError>(), Some(SpecError::ReadOnly))
+    };
+    assert!(is_read_only(
+        spec_db.create_specfile(&specfile).unwrap_err()
+    ));
+    assert!(is_read_only(
+        spec_db.update_specfile(id, &specfile).unwrap_err()
+    ));
+    assert!(is_read_only(spec_db.delete_specfile(id).unwrap_err()));
+    assert!(is_read_only(spec_db.undo_last().unwrap_err()));
+    assert!(spec_db
+        .connection()
+        .execute("DELETE FROM specfiles", [])
+        .is_err());
+    spec_db.close().unwrap();
+
+    // A query timeout still applies to searches
+    let spec_db = SpecBase::builder()
+        .path(&db_path)
+        .query_timeout(Duration::ZERO)
+        .open_immutable()
+        .unwrap();
+    let error = spec_db.query_specfiles("padding").unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::QueryTimeout(_))
+    ));
+    assert!(is_read_only(spec_db.delete_specfile(id).unwrap_err()));
+    spec_db.close().unwrap();
+
+    // Nothing changed, so the database still opens normally
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    assert_eq!(spec_db.read_specfile(id).unwrap().content, "Read only");
+
+    // Missing files are not created, and old schemas can't be migrated
+    let error = SpecBase::init_immutable(temp_dir.path().join("missing.db"))
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::DatabaseNotFound(_))
+    ));
+    assert!(!temp_dir.path().join("missing.db").exists());
+    let old_path = temp_dir.path().join("old.db");
+    let conn = rusqlite::Connection::open(&old_path).unwrap();
+    conn.pragma_update(None, "user_version", 1).unwrap();
+    drop(conn);
+    let error = SpecBase::init_immutable(&old_path).err().unwrap();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::OutdatedSchemaVersion { found: 1, required })
+            if *required == SCHEMA_VERSION
+    ));
+}
+
+#[test]
+fn test_vocabulary() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    spec_db
+        .create_specfile(&spec("A", "The Cache and the cache. CACHE rate-limit", &[]))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("B", "Rate limit the cache", &[]))
+        .unwrap();
+    let trashed = spec_db
+        .create_specfile(&spec("C", "trashed trashed trashed trashed trashed", &[]))
+        .unwrap();
+    spec_db.delete_specfile(trashed).unwrap();
+
+    let words = |top_n, exclude_stop_words| spec_db.vocabulary(top_n, exclude_stop_words).unwrap();
+    let pairs = |expected: &[(&str, u64)]| {
+        expected
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect::<Vec<_>>()
+    };
+    // Words are case-folded, and equal counts are ordered alphabetically
+    assert_eq!(
+        words(10, false),
+        pairs(&[
+            ("cache", 4),
+            ("the", 3),
+            ("limit", 2),
+            ("rate", 2),
+            ("and", 1)
+        ])
+    );
+    assert_eq!(
+        words(10, true),
+        pairs(&[("cache", 4), ("limit", 2), ("rate", 2)])
+    );
+    assert_eq!(words(2, true), pairs(&[("cache", 4), ("limit", 2)]));
+    assert!(words(0, true).is_empty());
+}
+
+#[test]
+fn test_word_frequencies() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&spec("A", "Retry the request; retry it AGAIN. Again?", &[]))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("B", "retry retry retry", &[]))
+        .unwrap();
+
+    let counts = spec_db.word_frequencies(id, 10, true).unwrap();
+    assert_eq!(
+        counts,
+        [
+            ("again".to_string(), 2),
+            ("retry".to_string(), 2),
+            ("request".to_string(), 1)
+        ]
+    );
+    assert_eq!(spec_db.word_frequencies(id, 1, false).unwrap().len(), 1);
+
+    let error = spec_db.word_frequencies(999, 10, true).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::SpecfileNotFound(999))
+    ));
+}
+
+#[test]
+fn test_pragmas() {
+    let temp_dir = tempdir().unwrap();
+    let spec_db = SpecBase::init_at(temp_dir.path().join("specbase.db")).unwrap();
+    let pragmas = spec_db.pragmas().unwrap();
+    assert_eq!(pragmas.journal_mode, "delete");
+    assert!((0..=3).contains(&pragmas.synchronous));
+    assert!(pragmas.busy_timeout >= 0);
+    assert_ne!(pragmas.cache_size, 0);
+    assert!(pragmas.page_size >= 512 && (pragmas.page_size as u64).is_power_of_two());
+
+    spec_db
+        .connection()
+        .pragma_update(None, "journal_mode", "wal")
+        .unwrap();
+    assert_eq!(spec_db.pragmas().unwrap().journal_mode, "wal");
+}
+
+#[test]
+fn test_checkpoint() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("specbase.db");
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+
+    // Without a write-ahead log there is nothing to checkpoint
+    let result = spec_db.checkpoint(CheckpointMode::Passive).unwrap();
+    assert!(result.log_pages < 0);
+    assert_eq!(result.remaining_pages(), 0);
+
+    spec_db
+        .connection()
+        .pragma_update(None, "journal_mode", "wal")
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("Logged", "In the WAL", &[]))
+        .unwrap();
+    let wal_path = temp_dir.path().join("specbase.db-wal");
+    assert!(fs::metadata(&wal_path).unwrap().len() > 0);
+
+    let result = spec_db.checkpoint(CheckpointMode::Truncate).unwrap();
+    assert!(!result.busy);
+    assert_eq!(result.remaining_pages(), 0);
+    assert_eq!(fs::metadata(&wal_path).unwrap().len(), 0);
+    assert_eq!(spec_db.count_specfiles().unwrap(), 1);
+}
+
+#[test]
+fn test_read_by_name_glob() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    for name in [
+        "API-auth",
+        "api-billing",
+        "API-",
+        "APIs",
+        "100%_done",
+        "1000 done",
+    ] {
+        spec_db.create_specfile(&spec(name, "", &[])).unwrap();
+    }
+    let names = |pattern: &str| -> Vec<String> {
+        spec_db
+            .read_by_name_glob(pattern)
+            .unwrap()
+            .into_iter()
+            .map(|specfile| specfile.name)
+            .collect()
+    };
+
+    // `*` matches any run of characters, including none, case-insensitively
+    assert_eq!(names("api-*"), ["API-auth", "api-billing", "API-"]);
+    assert_eq!(names("*-b*"), ["api-billing"]);
+    // `?` matches exactly one character
+    assert_eq!(names("API?"), ["API-", "APIs"]);
+    assert!(names("API??").is_empty());
+    // `%` and `_` are matched literally instead of as LIKE wildcards
+    assert_eq!(names("100%_done"), ["100%_done"]);
+    assert_eq!(names("10*_*"), ["100%_done"]);
+    assert!(names("%").is_empty());
+}
+
+#[test]
+fn test_find_suspicious_encoding() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let accented = spec_db
+        .create_specfile(&spec("Accented", "Café and “quotes” are fine", &[]))
+        .unwrap();
+    let double_encoded = spec_db
+        .create_specfile(&spec("Mojibake", "CafÃ© and itâ€™s broken", &[]))
+        .unwrap();
+    let trashed = spec_db
+        .create_specfile(&spec("Trashed", "dÃ©jÃ  vu", &[]))
+        .unwrap();
+    spec_db.delete_specfile(trashed).unwrap();
+
+    let suspicious = spec_db.find_suspicious_encoding().unwrap();
+    assert_eq!(suspicious, [double_encoded]);
+    assert!(!suspicious.contains(&accented));
+}
+
+#[test]
+fn test_regenerate_slugs() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let first = spec_db.create_specfile(&spec("Login", "", &[])).unwrap();
+    let second = spec_db.create_specfile(&spec("Login", "", &[])).unwrap();
+    let third = spec_db
+        .create_specfile(&spec("Rate Limits", "", &[]))
+        .unwrap();
+    assert_eq!(spec_db.regenerate_slugs().unwrap(), 0);
+
+    // Swapped and missing slugs are recomputed, oldest specfile first
+    spec_db
+        .connection()
+        .execute_batch(&format!(
+            "UPDATE specfiles SET slug = NULL WHERE id IN ({first}, {third});
+             UPDATE specfiles SET slug = 'login' WHERE id = {second};
+             UPDATE specfiles SET slug = 'login-2' WHERE id = {first};"
+        ))
+        .unwrap();
+    assert_eq!(spec_db.regenerate_slugs().unwrap(), 3);
+    assert_eq!(spec_db.slug(first).unwrap().unwrap(), "login");
+    assert_eq!(spec_db.slug(second).unwrap().unwrap(), "login-2");
+    assert_eq!(spec_db.slug(third).unwrap().unwrap(), "rate-limits");
+    assert_eq!(spec_db.regenerate_slugs().unwrap(), 0);
+}
+
+#[test]
+fn test_index_by_initial() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    for name in ["auth", "Audit", "billing", "2FA", "_draft", ""] {
+        spec_db.create_specfile(&spec(name, "", &[])).unwrap();
+    }
+
+    // Letters fold to uppercase, everything else goes under '#'
+    let index = spec_db.index_by_initial().unwrap();
+    assert_eq!(
+        index.into_iter().collect::<Vec<_>>(),
+        [('#', 3), ('A', 2), ('B', 1)]
+    );
+}
+
+#[test]
+fn test_sample() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    assert!(spec_db.sample(3).unwrap().is_empty());
+    let ids = spec_db
+        .create_specfiles(&[spec("A", "", &[]), spec("B", "", &[]), spec("C", "", &[])])
+        .unwrap();
+
+    // Asking for more than there are returns each specfile once
+    let mut sampled: Vec<i64> = spec_db
+        .sample(10)
+        .unwrap()
+        .iter()
+        .map(|specfile| specfile.id.unwrap())
+        .collect();
+    sampled.sort_unstable();
+    assert_eq!(sampled, ids);
+    assert_eq!(spec_db.sample(2).unwrap().len(), 2);
+    assert!(spec_db.sample(0).unwrap().is_empty());
+}
+
+#[test]
+fn test_grep_in_specfile() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "Large".to_string(),
+            description: String::new(),
+            content: "# Overview\nTODO: intro\n\nLimit is 100 req/s\nTODO: design".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+
+    let todos = spec_db.grep_in_specfile(id, "TODO", false).unwrap();
+    assert_eq!(
+        todos,
+        vec![
+            (2, "TODO: intro".to_string()),
+            (5, "TODO: design".to_string())
+        ]
+    );
+    // Literal matching doesn't interpret regex syntax
+    assert!(spec_db
+        .grep_in_specfile(id, "1.0", false)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        spec_db.grep_in_specfile(id, r"\d+ req", true).unwrap(),
+        vec![(4, "Limit is 100 req/s".to_string())]
+    );
+    assert!(spec_db.grep_in_specfile(id, "(", true).is_err());
+    assert!(spec_db.grep_in_specfile(999, "TODO", false).is_err());
+}
+
+#[test]
+fn test_tags() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let login = spec_db
+        .create_specfile(&spec("Login", "", &["billing", " auth ", "auth", ""]))
+        .unwrap();
+    let invoices = spec_db
+        .create_specfile(&spec("Invoices", "", &["billing"]))
+        .unwrap();
+    let untagged = spec_db.create_specfile(&spec("Untagged", "", &[])).unwrap();
+
+    assert_eq!(
+        spec_db.read_specfile(login).unwrap().tags,
+        ["auth", "billing"]
+    );
+    assert!(spec_db.read_specfile(untagged).unwrap().tags.is_empty());
+    let billing = spec_db.list_specfiles_by_tag("billing").unwrap();
+    let ids: Vec<i64> = billing.iter().map(|spec| spec.id.unwrap()).collect();
+    assert_eq!(ids, [login, invoices]);
+
+    // Updating replaces the tags
+    spec_db
+        .update_specfile(login, &spec("Login", "", &["security"]))
+        .unwrap();
+    assert!(spec_db.list_specfiles_by_tag("auth").unwrap().is_empty());
+    assert_eq!(spec_db.list_specfiles_by_tag("security").unwrap().len(), 1);
+
+    spec_db.delete_specfile(invoices).unwrap();
+    assert!(spec_db.list_specfiles_by_tag("billing").unwrap().is_empty());
+}
+
+#[test]
+fn test_related_by_tags() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let login = spec_db
+        .create_specfile(&spec("Login", "", &["auth", "security", "web"]))
+        .unwrap();
+    let sessions = spec_db
+        .create_specfile(&spec("Sessions", "", &["web"]))
+        .unwrap();
+    let tokens = spec_db
+        .create_specfile(&spec("Tokens", "", &["auth", "security"]))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("Invoices", "", &["billing"]))
+        .unwrap();
+
+    let related = |limit| -> Vec<(i64, u64)> {
+        let related = spec_db.related_by_tags(login, limit).unwrap();
+        related
+            .into_iter()
+            .map(|(spec, shared)| (spec.id.unwrap(), shared))
+            .collect()
+    };
+    assert_eq!(related(10), [(tokens, 2), (sessions, 1)]);
+    assert_eq!(related(1), [(tokens, 2)]);
+
+    assert_eq!(
+        spec_db.related_by_tags(sessions, 10).unwrap()[0].0.name,
+        "Login"
+    );
+    assert!(spec_db.related_by_tags(login + 10, 10).is_err());
+}
+
+#[test]
+fn test_trash() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let login = spec_db
+        .create_specfile(&spec("Login", "Login content", &["auth"]))
+        .unwrap();
+    let invoices = spec_db
+        .create_specfile(&spec("Invoices", "Invoices content", &["billing"]))
+        .unwrap();
+
+    // Deleted specfiles are hidden but kept in the trash
+    spec_db.delete_specfile(login).unwrap();
+    assert!(spec_db.read_specfile(login).is_err());
+    assert_eq!(spec_db.list_specfiles().unwrap().len(), 1);
+    assert!(spec_db.query_specfiles("Login").unwrap().is_empty());
+    assert!(spec_db.search_specfiles("login").unwrap().is_empty());
+    assert!(spec_db.delete_specfile(login).is_err());
+    let deleted = spec_db.list_deleted().unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].0.id, Some(login));
+
+    // Restoring brings back the specfile with its tags
+    spec_db.restore_specfile(login).unwrap();
+    assert_eq!(spec_db.read_specfile(login).unwrap().tags, ["auth"]);
+    assert_eq!(spec_db.search_specfiles("login").unwrap().len(), 1);
+    assert!(spec_db.restore_specfile(login).is_err());
+    assert!(spec_db.list_deleted().unwrap().is_empty());
+
+    // Purging removes only trashed specfiles, and their unused tags
+    spec_db.delete_specfile(invoices).unwrap();
+    assert_eq!(spec_db.purge_deleted().unwrap(), 1);
+    assert_eq!(spec_db.purge_deleted().unwrap(), 0);
+    assert!(spec_db.restore_specfile(invoices).is_err());
+    assert!(spec_db.list_specfiles_by_tag("billing").unwrap().is_empty());
+    assert_eq!(spec_db.count_specfiles().unwrap(), 1);
+}
+
+#[test]
+fn test_diff_database() {
+    let temp_dir = tempdir().unwrap();
+    let other_path = temp_dir.path().join("shared.db");
+    let other_db = SpecBase::init_at(&other_path).unwrap();
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    for (db, specs) in [
+        (
+            &spec_db,
+            [
+                ("Local", "Only here"),
+                ("Login", "New login"),
+                ("Same", "Equal"),
+                ("Twice", "A"),
+                ("Twice", "B"),
+            ],
+        ),
+        (
+            &other_db,
+            [
+                ("Remote", "Only there"),
+                ("Login", "Old login"),
+                ("Same", "Equal"),
+                ("Twice", "B"),
+                ("Twice", "A"),
+            ],
+        ),
+    ] {
+        for (name, content) in specs {
+            db.create_specfile(&spec(name, content, &[])).unwrap();
+        }
+    }
+    other_db.close().unwrap();
+
+    // Duplicate names compare their contents as a set, regardless of order
+    let diff = spec_db.diff_database(&other_path).unwrap();
+    assert_eq!(diff.only_in_self, ["Local"]);
+    assert_eq!(diff.only_in_other, ["Remote"]);
+    assert_eq!(diff.changed, ["Login"]);
+
+    spec_db.create_specfile(&spec("Twice", "C", &[])).unwrap();
+    let diff = spec_db.diff_database(&other_path).unwrap();
+    assert_eq!(diff.changed, ["Login", "Twice"]);
+    assert!(spec_db
+        .diff_database(temp_dir.path().join("missing.db"))
+        .is_err());
+}
+
+#[test]
+fn test_diff_database_with_baseline_schema() {
+    // Databases from before the trash have no deleted_at column and are
+    // never migrated by a diff, since they are opened read-only
+    let temp_dir = tempdir().unwrap();
+    let other_path = temp_dir.path().join("shared.db");
+    let other = rusqlite::Connection::open(&other_path).unwrap();
+    other
+        .execute_batch(
+            "CREATE TABLE specfiles (
+                 id INTEGER PRIMARY KEY,
+                 name TEXT NOT NULL,
+                 description TEXT NOT NULL,
+                 content TEXT NOT NULL
+             );
+             INSERT INTO specfiles (name, description, content) VALUES
+                 ('Login', '', 'Old login'),
+                 ('Shared', '', 'Same');",
+        )
+        .unwrap();
+    drop(other);
+
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    spec_db
+        .create_specfile(&spec("Login", "New login", &[]))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec("Shared", "Same", &[]))
+        .unwrap();
+    let trashed = spec_db
+        .create_specfile(&spec("Trashed", "Gone", &[]))
+        .unwrap();
+    spec_db.delete_specfile(trashed).unwrap();
+
+    let diff = spec_db.diff_database(&other_path).unwrap();
+    assert!(diff.only_in_self.is_empty());
+    assert!(diff.only_in_other.is_empty());
+    assert_eq!(diff.changed, ["Login"]);
+
+    let other = rusqlite::Connection::open(&other_path).unwrap();
+    let version: i64 = other
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 0);
+}
+
+#[test]
+fn test_undo_last() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let draft = |name: &str| Specfile {
+        description: "Draft".to_string(),
+        ..spec(name, &format!("{name} content"), &["keep"])
+    };
+    let login = spec_db.create_specfile(&draft("Login")).unwrap();
+    let invoices = spec_db.create_specfile(&draft("Invoices")).unwrap();
+    let nothing_to_undo = |result: anyhow::Result<()>| {
+        matches!(
+            result.unwrap_err().downcast_ref::<SpecError>(),
+            Some(SpecError::NothingToUndo)
+        )
+    };
+    assert!(nothing_to_undo(spec_db.undo_last()));
+
+    // A bulk update is reverted for every specfile it changed
+    let patch = SpecfilePatch {
+        description: Some("Reviewed".to_string()),
+        ..Default::default()
+    };
+    spec_db.patch_many(&[login, invoices], patch).unwrap();
+    spec_db.undo_last().unwrap();
+    for id in [login, invoices] {
+        assert_eq!(spec_db.read_specfile(id).unwrap().description, "Draft");
+    }
+    assert!(nothing_to_undo(spec_db.undo_last()));
+
+    // Purged specfiles come back with their tags, and in the trash
+    spec_db.delete_specfile(login).unwrap();
+    spec_db.purge_deleted().unwrap();
+    spec_db.undo_last().unwrap();
+    assert_eq!(spec_db.list_deleted().unwrap()[0].0.tags, ["keep"]);
+    assert_eq!(spec_db.search_specfiles("login").unwrap().len(), 0);
+    spec_db.restore_specfile(login).unwrap();
+    assert_eq!(spec_db.search_specfiles("login").unwrap().len(), 1);
+
+    // Any later change discards the journal
+    spec_db.delete_specfile(invoices).unwrap();
+    spec_db.restore_specfile(invoices).unwrap();
+    assert!(nothing_to_undo(spec_db.undo_last()));
+    spec_db.delete_specfile(invoices).unwrap();
+    spec_db.create_specfile(&draft("Later")).unwrap();
+    assert!(nothing_to_undo(spec_db.undo_last()));
+    assert!(spec_db.get_specfile(invoices).unwrap().is_none());
+}
+
+#[test]
+fn test_json_round_trip() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let spec = Specfile {
+        id: None,
+        name: "Backup".to_string(),
+        description: "Tagged".to_string(),
+        content: "# Backup".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: vec!["auth".to_string(), "billing".to_string()],
+    };
+    spec_db.create_specfile(&spec).unwrap();
+
+    let mut json = Vec::new();
+    assert_eq!(spec_db.export_json(&mut json).unwrap(), 1);
+
+    let restored_db = SpecBase::init_in_memory().unwrap();
+    let ids = restored_db.import_json(json.as_slice()).unwrap();
+    let restored = restored_db.read_specfile(ids[0]).unwrap();
+    assert_eq!(restored.version(), spec.version());
+    assert_eq!(restored_db.list_specfiles_by_tag("auth").unwrap().len(), 1);
+
+    // Fields added later are optional in the input
+    let ids = restored_db
+        .import_json(
+            r#"[{"id": null, "name": "Old", "description": "", "content": ""}]"#.as_bytes(),
+        )
+        .unwrap();
+    assert!(restored_db.read_specfile(ids[0]).unwrap().tags.is_empty());
+    assert!(restored_db.import_json("{}".as_bytes()).is_err());
+
+    let by_id = restored_db.list_as_map().unwrap();
+    assert_eq!(by_id.len(), 2);
+    assert_eq!(by_id[&ids[0]].name, "Old");
+    let json = serde_json::to_value(&by_id).unwrap();
+    assert_eq!(json[ids[0].to_string()]["name"], "Old");
+}
+
+#[test]
+fn test_search_specfiles() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let mentions = spec_db
+        .create_specfile(&spec(
+            "Mentions",
+            "The cache is mentioned once among many other words here",
+            &[],
+        ))
+        .unwrap();
+    let about = spec_db
+        .create_specfile(&spec(
+            "Cache",
+            "Cache eviction: the cache evicts old entries",
+            &[],
+        ))
+        .unwrap();
+    spec_db
+        .create_specfile(&spec(
+            "Caching",
+            "Substrings like caches are not words",
+            &[],
+        ))
+        .unwrap();
+
+    let ids: Vec<i64> = spec_db
+        .search_specfiles("CACHE")
+        .unwrap()
+        .iter()
+        .map(|spec| spec.id.unwrap())
+        .collect();
+    assert_eq!(ids, [about, mentions]);
+    assert_eq!(spec_db.search_specfiles("eviction cache").unwrap().len(), 1);
+    assert_eq!(spec_db.search_specfiles("cache-eviction").unwrap().len(), 1);
+    assert!(spec_db.search_specfiles("\"*").unwrap().is_empty());
+
+    // The index follows updates and deletes
+    spec_db
+        .update_specfile(about, &spec("Cache", "Replaced", &[]))
+        .unwrap();
+    assert!(spec_db.search_specfiles("eviction").unwrap().is_empty());
+    spec_db.delete_specfile(mentions).unwrap();
+    assert_eq!(spec_db.search_specfiles("cache").unwrap().len(), 1);
+
+    // Existing specfiles are indexed when the index is added
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("old.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE specfiles (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            content TEXT NOT NULL
+        );
+        INSERT INTO specfiles (name, description, content) VALUES ('Old', '', 'legacy text');",
+    )
+    .unwrap();
+    drop(conn);
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    assert_eq!(spec_db.search_specfiles("legacy").unwrap().len(), 1);
+}
+
+#[test]
+fn test_cursor_loads_content_on_demand() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    for (name, tags) in [("First", vec![]), ("Second", vec!["auth".to_string()])] {
+        spec_db
+            .create_specfile(&Specfile {
+                id: None,
+                name: name.to_string(),
+                description: String::new(),
+                content: format!("Content of {}", name),
+                format_version: CURRENT_FORMAT_VERSION,
+                created_at: None,
+                updated_at: None,
+                tags,
+            })
+            .unwrap();
+    }
+
+    let cursor = spec_db.cursor().unwrap();
+    assert_eq!(cursor.len(), 2);
+    let summaries: Vec<_> = cursor.collect();
+    assert_eq!(summaries[0].name, "First");
+    assert_eq!(summaries[1].tags, ["auth"]);
+    assert_eq!(summaries[1].load_content().unwrap(), "Content of Second");
+
+    spec_db.delete_specfile(summaries[0].id).unwrap();
+    assert!(summaries[0].load_content().is_err());
+}
+
+#[test]
+fn test_query_escapes_like_wildcards() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let mut ids = Vec::new();
+    for content in ["100% done", "1000 done", "snake_case", "back\\slash"] {
+        let id = spec_db
+            .create_specfile(&Specfile {
+                id: None,
+                name: "Progress".to_string(),
+                description: String::new(),
+                content: content.to_string(),
+                format_version: CURRENT_FORMAT_VERSION,
+                created_at: None,
+                updated_at: None,
+                tags: Vec::new(),
+            })
+            .unwrap();
+        ids.push(id);
+    }
+
+    assert_eq!(spec_db.query_ids("100%").unwrap(), [ids[0]]);
+    assert_eq!(spec_db.query_ids("e_c").unwrap(), [ids[2]]);
+    assert_eq!(spec_db.query_ids("k\\s").unwrap(), [ids[3]]);
+    assert_eq!(spec_db.count_query("%").unwrap(), 1);
+}
+
+#[test]
+fn test_metadata_json() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let id = spec_db
+        .create_specfile(&Specfile {
+            id: None,
+            name: "With metadata".to_string(),
+            description: String::new(),
+            content: String::new(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .unwrap();
+    assert_eq!(spec_db.get_metadata_json(id).unwrap(), None);
+
+    let ticket = serde_json::json!({ "key": "API-42", "labels": ["auth", "v2"] });
+    spec_db.set_metadata_json(id, &ticket).unwrap();
+    assert_eq!(spec_db.get_metadata_json(id).unwrap(), Some(ticket));
+
+    spec_db
+        .set_metadata_json(id, &serde_json::Value::Null)
+        .unwrap();
+    assert_eq!(spec_db.get_metadata_json(id).unwrap(), None);
+
+    assert!(spec_db.get_metadata_json(id + 1).is_err());
+    assert!(spec_db
+        .set_metadata_json(id + 1, &serde_json::json!(1))
+        .is_err());
+}
+
+#[test]
+fn test_timeline() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let mut specfile = Specfile {
+        id: None,
+        name: "First".to_string(),
+        description: String::new(),
+        content: "one".to_string(),
+        format_version: CURRENT_FORMAT_VERSION,
+        created_at: None,
+        updated_at: None,
+        tags: Vec::new(),
+    };
+    let first = spec_db.create_specfile(&specfile).unwrap();
+    specfile.name = "Second".to_string();
+    let second = spec_db.create_specfile(&specfile).unwrap();
+
+    let events = |spec_db: &SpecBase| -> Vec<(i64, TimelineEvent)> {
+        spec_db
+            .timeline()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.specfile_id, entry.event))
+            .collect()
+    };
+    assert_eq!(
+        events(&spec_db),
+        [
+            (first, TimelineEvent::Created),
+            (second, TimelineEvent::Created)
+        ]
+    );
+
+    // Timestamps have a resolution of one second
+    std::thread::sleep(Duration::from_millis(1100));
+    specfile.name = "First, revised".to_string();
+    spec_db.update_specfile(first, &specfile).unwrap();
+    assert_eq!(
+        events(&spec_db),
+        [
+            (first, TimelineEvent::Created),
+            (second, TimelineEvent::Created),
+            (first, TimelineEvent::Updated)
+        ]
+    );
+    assert_eq!(spec_db.timeline().unwrap()[2].name, "First, revised");
+}
+
+#[test]
+fn test_tag_growth() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("specbase.db");
+    let spec_db = SpecBase::init_at(&db_path).unwrap();
+    let created = [
+        ("Login", &["auth"][..], "2024-01-01T09:00:00Z"),
+        ("Logout", &["auth"][..], "2024-01-01T17:30:00Z"),
+        ("Tokens", &["auth", "api"][..], "2024-01-03T08:00:00Z"),
+        ("Invoices", &["billing"][..], "2024-01-02T12:00:00Z"),
+        ("Sessions", &["auth"][..], "2024-01-04T10:00:00Z"),
+    ];
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let mut ids = Vec::new();
+    for (name, tags, created_at) in created {
+        let id = spec_db.create_specfile(&spec(name, "", tags)).unwrap();
+        conn.execute(
+            "UPDATE specfiles SET created_at = ?1 WHERE id = ?2",
+            rusqlite::params![created_at, id],
+        )
+        .unwrap();
+        ids.push(id);
+    }
+    spec_db.delete_specfile(ids[4]).unwrap();
+
+    assert_eq!(
+        spec_db.tag_growth(" auth ").unwrap(),
+        [("2024-01-01".to_string(), 2), ("2024-01-03".to_string(), 1)]
+    );
+    assert!(spec_db.tag_growth("unused").unwrap().is_empty());
+}
+
+#[test]
+fn test_query_page() {
+    let spec_db = SpecBase::init_in_memory().unwrap();
+    let specs: Vec<Specfile> = ["delta", "Alpha", "charlie", "Bravo", "echo"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Specfile {
+            id: None,
+            name: name.to_string(),
+            description: String::new(),
+            content: if i % 2 == 0 { "Rate limits" } else { "Caching" }.to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: if i < 4 {
+                vec!["api".to_string()]
+            } else {
+                Vec::new()
+            },
+        })
+        .collect();
+    let ids = spec_db.create_specfiles(&specs).unwrap();
+    spec_db.delete_specfile(ids[1]).unwrap();
+
+    let names = |params: QueryParams| {
+        let result = spec_db.query_page(params).unwrap();
+        let names: Vec<String> = result.items.into_iter().map(|item| item.name).collect();
+        (names, result.total)
+    };
+    assert_eq!(
+        names(QueryParams::default()),
+        (
+            vec!["delta", "charlie", "Bravo", "echo"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            4
+        )
+    );
+
+    // Filters are combined, and the total counts every page
+    let params = QueryParams {
+        query: Some("rate".to_string()),
+        tags: vec!["api".to_string()],
+        sort: SortKey::Name,
+        direction: SortDirection::Descending,
+        per_page: 1,
+        ..QueryParams::default()
+    };
+    assert_eq!(names(params.clone()), (vec!["delta".to_string()], 2));
+    let second = QueryParams {
+        page: 2,
+        ..params.clone()
+    };
+    assert_eq!(names(second), (vec!["charlie".to_string()], 2));
+    let past_end = QueryParams { page: 3, ..params };
+    let result = spec_db.query_page(past_end).unwrap();
+    assert!(result.items.is_empty());
+    assert_eq!((result.total, result.page_count()), (2, 2));
+
+    // Names sort case-insensitively, and summaries load content on demand
+    let result = spec_db
+        .query_page(QueryParams {
+            sort: SortKey::Name,
+            per_page: 3,
+            ..QueryParams::default()
+        })
+        .unwrap();
+    let sorted: Vec<&str> = result.items.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(sorted, ["Bravo", "charlie", "delta"]);
+    assert_eq!(result.items[0].tags, ["api"]);
+    assert_eq!(result.items[0].load_content().unwrap(), "Caching");
+}
+
+#[test]
+fn test_query_timeout() {
+    let specs: Vec<Specfile> = (0..200)
+        .map(|i| Specfile {
+            id: None,
+            name: format!("Spec {}", i),
+            description: String::new(),
+            content: "Rate limits per user".to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            created_at: None,
+            updated_at: None,
+            tags: Vec::new(),
+        })
+        .collect();
+
+    // With a zero limit every search is aborted, but writes are not
+    let spec_db = SpecBase::builder()
+        .query_timeout(Duration::ZERO)
+        .init_in_memory()
+        .unwrap();
+    let ids = spec_db.create_specfiles(&specs).unwrap();
+    let error = spec_db.query_specfiles("rate").unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<SpecError>(),
+        Some(SpecError::QueryTimeout(limit)) if limit.is_zero()
+    ));
+    assert!(spec_db.list_specfiles().is_err());
+    assert_eq!(spec_db.read_specfile(ids[0]).unwrap().name, "Spec 0");
+
+    let spec_db = SpecBase::builder()
+        .query_timeout(Duration::from_secs(60))
+        .init_in_memory()
+        .unwrap();
+    spec_db.create_specfiles(&specs).unwrap();
+    assert_eq!(spec_db.query_ids("rate").unwrap().len(), 200);
+}